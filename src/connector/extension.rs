@@ -1,22 +1,30 @@
 use super::{
     message::{
-        build_discovery_get_nodes, build_discovery_nodes, build_identify_message,
-        build_relay_transaction, build_relay_transaction_hashes,
+        build_discovery_get_nodes, build_discovery_nodes, build_filter_block_filter_check_points,
+        build_filter_block_filter_hashes, build_filter_block_filters,
+        build_filter_get_block_filter_check_points, build_filter_get_block_filter_hashes,
+        build_filter_get_block_filters, build_identify_message, build_relay_block_proposal,
+        build_relay_block_transactions, build_relay_compact_block, build_relay_get_block_proposal,
+        build_relay_get_block_transactions, build_relay_get_relay_transactions,
+        build_relay_transaction, build_relay_transaction_hashes, build_sync_block,
+        build_sync_get_blocks, build_sync_get_headers, build_sync_headers, build_sync_in_ibd,
     },
     Connector, SupportProtocols,
 };
-use crate::Node;
+use crate::{Error, Node};
 use ckb_types::{
     bytes::Bytes,
-    core::{Cycle, TransactionView},
+    core::{BlockNumber, BlockView, Cycle, HeaderView, TransactionView, UncleBlockView},
     packed,
     prelude::*,
 };
+use crossbeam::channel::{Receiver, RecvTimeoutError};
 use tentacle::multiaddr::Multiaddr;
 /// Util functions attached to `Connector`.
 use std::time::Duration;
 
 impl Connector {
+    /// Send a `RelayTransactions` of `transaction` with `cycles` under `relay_protocol`
     pub fn send_relay_transaction(
         &self,
         node: &Node,
@@ -24,30 +32,255 @@ impl Connector {
         transaction: &TransactionView,
         cycles: Cycle,
     ) -> Result<(), String> {
-        assert!(
-            relay_protocol.protocol_id() == SupportProtocols::Relay.protocol_id()
-                || relay_protocol.protocol_id() == SupportProtocols::RelayV2.protocol_id()
-        );
+        assert_relay_protocol(&relay_protocol);
         let message = build_relay_transaction(transaction, cycles);
         self.send(&node, relay_protocol, message.as_bytes())?;
         Ok(())
     }
 
+    /// Send a `RelayTransactionHashes` of `hashes` under `relay_protocol`
     pub fn send_relay_transaction_hash(
         &self,
         node: &Node,
         relay_protocol: SupportProtocols,
         hashes: Vec<packed::Byte32>,
     ) -> Result<(), String> {
-        assert!(
-            relay_protocol.protocol_id() == SupportProtocols::Relay.protocol_id()
-                || relay_protocol.protocol_id() == SupportProtocols::RelayV2.protocol_id()
-        );
+        assert_relay_protocol(&relay_protocol);
         let message = build_relay_transaction_hashes(hashes);
         self.send(node, relay_protocol, message.as_bytes())?;
         Ok(())
     }
 
+    /// Send a `CompactBlock` of `block` under `relay_protocol`, see `build_relay_compact_block`
+    pub fn send_relay_compact_block(
+        &self,
+        node: &Node,
+        relay_protocol: SupportProtocols,
+        block: &BlockView,
+        prefilled_indexes: &[usize],
+    ) -> Result<(), String> {
+        assert_relay_protocol(&relay_protocol);
+        let message = build_relay_compact_block(block, prefilled_indexes);
+        self.send(node, relay_protocol, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `GetBlockTransactions` of `block_hash` under `relay_protocol`
+    pub fn send_relay_get_block_transactions(
+        &self,
+        node: &Node,
+        relay_protocol: SupportProtocols,
+        block_hash: packed::Byte32,
+        indexes: Vec<u32>,
+        uncle_indexes: Vec<u32>,
+    ) -> Result<(), String> {
+        assert_relay_protocol(&relay_protocol);
+        let message = build_relay_get_block_transactions(block_hash, indexes, uncle_indexes);
+        self.send(node, relay_protocol, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `BlockTransactions` of `block_hash` under `relay_protocol`
+    pub fn send_relay_block_transactions(
+        &self,
+        node: &Node,
+        relay_protocol: SupportProtocols,
+        block_hash: packed::Byte32,
+        transactions: &[TransactionView],
+        uncles: &[UncleBlockView],
+    ) -> Result<(), String> {
+        assert_relay_protocol(&relay_protocol);
+        let message = build_relay_block_transactions(block_hash, transactions, uncles);
+        self.send(node, relay_protocol, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `GetRelayTransactions` of `tx_hashes` under `relay_protocol`
+    pub fn send_relay_get_relay_transactions(
+        &self,
+        node: &Node,
+        relay_protocol: SupportProtocols,
+        tx_hashes: Vec<packed::Byte32>,
+    ) -> Result<(), String> {
+        assert_relay_protocol(&relay_protocol);
+        let message = build_relay_get_relay_transactions(tx_hashes);
+        self.send(node, relay_protocol, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `GetBlockProposal` of `block_hash` under `relay_protocol`
+    pub fn send_relay_get_block_proposal(
+        &self,
+        node: &Node,
+        relay_protocol: SupportProtocols,
+        block_hash: packed::Byte32,
+        proposals: Vec<packed::ProposalShortId>,
+    ) -> Result<(), String> {
+        assert_relay_protocol(&relay_protocol);
+        let message = build_relay_get_block_proposal(block_hash, proposals);
+        self.send(node, relay_protocol, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `BlockProposal` of `transactions` under `relay_protocol`
+    pub fn send_relay_block_proposal(
+        &self,
+        node: &Node,
+        relay_protocol: SupportProtocols,
+        transactions: &[TransactionView],
+    ) -> Result<(), String> {
+        assert_relay_protocol(&relay_protocol);
+        let message = build_relay_block_proposal(transactions);
+        self.send(node, relay_protocol, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Receive the relay messages until a `CompactBlock` arrives, and return it
+    pub fn recv_relay_compact_block(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::CompactBlock, String> {
+        self.try_recv_relay_compact_block(node, relay_protocol, timeout)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn try_recv_relay_compact_block(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::CompactBlock, Error> {
+        self.try_recv_relay(node, relay_protocol, timeout, |message| match message {
+            packed::RelayMessageUnion::CompactBlock(compact_block) => Some(compact_block),
+            _ => None,
+        })
+    }
+
+    /// Receive the relay messages until a `GetBlockTransactions` arrives, and return it
+    pub fn recv_relay_get_block_transactions(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::GetBlockTransactions, String> {
+        self.try_recv_relay_get_block_transactions(node, relay_protocol, timeout)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn try_recv_relay_get_block_transactions(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::GetBlockTransactions, Error> {
+        self.try_recv_relay(node, relay_protocol, timeout, |message| match message {
+            packed::RelayMessageUnion::GetBlockTransactions(get_block_transactions) => {
+                Some(get_block_transactions)
+            }
+            _ => None,
+        })
+    }
+
+    /// Receive the relay messages until a `BlockTransactions` arrives, and return it
+    pub fn recv_relay_block_transactions(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::BlockTransactions, String> {
+        self.try_recv_relay_block_transactions(node, relay_protocol, timeout)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn try_recv_relay_block_transactions(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::BlockTransactions, Error> {
+        self.try_recv_relay(node, relay_protocol, timeout, |message| match message {
+            packed::RelayMessageUnion::BlockTransactions(block_transactions) => {
+                Some(block_transactions)
+            }
+            _ => None,
+        })
+    }
+
+    /// Receive the relay messages until a `GetRelayTransactions` arrives, and return it
+    pub fn recv_relay_get_relay_transactions(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::GetRelayTransactions, String> {
+        self.try_recv_relay_get_relay_transactions(node, relay_protocol, timeout)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn try_recv_relay_get_relay_transactions(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::GetRelayTransactions, Error> {
+        self.try_recv_relay(node, relay_protocol, timeout, |message| match message {
+            packed::RelayMessageUnion::GetRelayTransactions(get_relay_transactions) => {
+                Some(get_relay_transactions)
+            }
+            _ => None,
+        })
+    }
+
+    /// Receive the relay messages until a `GetBlockProposal` arrives, and return it
+    pub fn recv_relay_get_block_proposal(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::GetBlockProposal, String> {
+        self.try_recv_relay_get_block_proposal(node, relay_protocol, timeout)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn try_recv_relay_get_block_proposal(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::GetBlockProposal, Error> {
+        self.try_recv_relay(node, relay_protocol, timeout, |message| match message {
+            packed::RelayMessageUnion::GetBlockProposal(get_block_proposal) => {
+                Some(get_block_proposal)
+            }
+            _ => None,
+        })
+    }
+
+    /// Receive the relay messages until a `BlockProposal` arrives, and return it
+    pub fn recv_relay_block_proposal(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::BlockProposal, String> {
+        self.try_recv_relay_block_proposal(node, relay_protocol, timeout)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn try_recv_relay_block_proposal(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+    ) -> Result<packed::BlockProposal, Error> {
+        self.try_recv_relay(node, relay_protocol, timeout, |message| match message {
+            packed::RelayMessageUnion::BlockProposal(block_proposal) => Some(block_proposal),
+            _ => None,
+        })
+    }
+
     pub fn send_identify_message(
         &self,
         node: &Node,
@@ -89,22 +322,134 @@ impl Connector {
         Ok(())
     }
 
+    /// Send a `GetHeaders` of `block_locator_hashes` and `hash_stop` under `SupportProtocols::Sync`
+    pub fn send_sync_get_headers(
+        &self,
+        node: &Node,
+        block_locator_hashes: Vec<packed::Byte32>,
+        hash_stop: packed::Byte32,
+    ) -> Result<(), String> {
+        let message = build_sync_get_headers(block_locator_hashes, hash_stop);
+        self.send(node, SupportProtocols::Sync, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `SendHeaders` of `headers` under `SupportProtocols::Sync`
+    pub fn send_sync_headers(&self, node: &Node, headers: &[HeaderView]) -> Result<(), String> {
+        let message = build_sync_headers(headers);
+        self.send(node, SupportProtocols::Sync, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `GetBlocks` of `block_hashes` under `SupportProtocols::Sync`
+    pub fn send_sync_get_blocks(
+        &self,
+        node: &Node,
+        block_hashes: Vec<packed::Byte32>,
+    ) -> Result<(), String> {
+        let message = build_sync_get_blocks(block_hashes);
+        self.send(node, SupportProtocols::Sync, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `SendBlock` of `block` under `SupportProtocols::Sync`
+    pub fn send_sync_block(&self, node: &Node, block: &BlockView) -> Result<(), String> {
+        let message = build_sync_block(block);
+        self.send(node, SupportProtocols::Sync, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Tell `node` that the connector is in IBD, so that `node` does not sync from it
+    pub fn send_sync_in_ibd(&self, node: &Node) -> Result<(), String> {
+        let message = build_sync_in_ibd();
+        self.send(node, SupportProtocols::Sync, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `GetBlockFilters` from `start_number` under `SupportProtocols::Filter`
+    pub fn send_filter_get_block_filters(
+        &self,
+        node: &Node,
+        start_number: BlockNumber,
+    ) -> Result<(), String> {
+        let message = build_filter_get_block_filters(start_number);
+        self.send(node, SupportProtocols::Filter, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `BlockFilters` under `SupportProtocols::Filter`
+    pub fn send_filter_block_filters(
+        &self,
+        node: &Node,
+        start_number: BlockNumber,
+        block_hashes: Vec<packed::Byte32>,
+        filters: Vec<Bytes>,
+    ) -> Result<(), String> {
+        let message = build_filter_block_filters(start_number, block_hashes, filters);
+        self.send(node, SupportProtocols::Filter, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `GetBlockFilterHashes` from `start_number` under `SupportProtocols::Filter`
+    pub fn send_filter_get_block_filter_hashes(
+        &self,
+        node: &Node,
+        start_number: BlockNumber,
+    ) -> Result<(), String> {
+        let message = build_filter_get_block_filter_hashes(start_number);
+        self.send(node, SupportProtocols::Filter, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `BlockFilterHashes` under `SupportProtocols::Filter`
+    pub fn send_filter_block_filter_hashes(
+        &self,
+        node: &Node,
+        start_number: BlockNumber,
+        parent_block_filter_hash: packed::Byte32,
+        block_filter_hashes: Vec<packed::Byte32>,
+    ) -> Result<(), String> {
+        let message = build_filter_block_filter_hashes(
+            start_number,
+            parent_block_filter_hash,
+            block_filter_hashes,
+        );
+        self.send(node, SupportProtocols::Filter, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `GetBlockFilterCheckPoints` from `start_number` under `SupportProtocols::Filter`
+    pub fn send_filter_get_block_filter_check_points(
+        &self,
+        node: &Node,
+        start_number: BlockNumber,
+    ) -> Result<(), String> {
+        let message = build_filter_get_block_filter_check_points(start_number);
+        self.send(node, SupportProtocols::Filter, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// Send a `BlockFilterCheckPoints` under `SupportProtocols::Filter`
+    pub fn send_filter_block_filter_check_points(
+        &self,
+        node: &Node,
+        start_number: BlockNumber,
+        block_filter_hashes: Vec<packed::Byte32>,
+    ) -> Result<(), String> {
+        let message = build_filter_block_filter_check_points(start_number, block_filter_hashes);
+        self.send(node, SupportProtocols::Filter, message.as_bytes())?;
+        Ok(())
+    }
+
     pub fn recv(&self, node: &Node, protocol: &SupportProtocols) -> Result<Bytes, String> {
-        let session = self.get_session(node).ok_or(format!(
-            "session to {} is notfound",
-            node.p2p_address_with_node_id()
-        ))?;
-        let receiver = {
-            let shared = self.shared.read().unwrap();
-            shared
-                .get_protocol_receiver(&session.id, &protocol.protocol_id())
-                .ok_or(format!(
-                    "protocol \"{}\" to {} is notfound",
-                    protocol.name(),
-                    node.p2p_address_with_node_id()
-                ))?
-        };
-        receiver.recv().map_err(|err| format!("{:?}", err))
+        self.try_recv(node, protocol).map_err(|err| err.to_string())
+    }
+
+    pub fn try_recv(&self, node: &Node, protocol: &SupportProtocols) -> Result<Bytes, Error> {
+        let receiver = self.protocol_receiver(node, protocol)?;
+        receiver
+            .recv()
+            .map_err(|err| Error::Disconnected(format!("{:?}", err)))
     }
 
     pub fn recv_timeout(
@@ -113,22 +458,81 @@ impl Connector {
         node: &Node,
         protocol: &SupportProtocols,
     ) -> Result<Bytes, String> {
-        let session = self.get_session(node).ok_or(format!(
-            "session to {} is notfound",
-            node.p2p_address_with_node_id()
-        ))?;
-        let receiver = {
-            let shared = self.shared.read().unwrap();
-            shared
-                .get_protocol_receiver(&session.id, &protocol.protocol_id())
-                .ok_or(format!(
-                    "protocol \"{}\" to {} is notfound",
-                    protocol.name(),
-                    node.p2p_address_with_node_id()
-                ))?
-        };
-        receiver
-            .recv_timeout(timeout)
-            .map_err(|err| format!("{:?}", err))
+        self.try_recv_timeout(timeout, node, protocol)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn try_recv_timeout(
+        &self,
+        timeout: Duration,
+        node: &Node,
+        protocol: &SupportProtocols,
+    ) -> Result<Bytes, Error> {
+        let receiver = self.protocol_receiver(node, protocol)?;
+        receiver.recv_timeout(timeout).map_err(|err| match err {
+            RecvTimeoutError::Timeout => Error::Timeout(format!(
+                "receive message under protocol \"{}\" from {}",
+                protocol.name(),
+                node.p2p_address_with_node_id()
+            )),
+            RecvTimeoutError::Disconnected => Error::Disconnected(format!("{:?}", err)),
+        })
     }
+
+    fn protocol_receiver(
+        &self,
+        node: &Node,
+        protocol: &SupportProtocols,
+    ) -> Result<Receiver<Bytes>, Error> {
+        let session = self.get_session(node).ok_or_else(|| {
+            Error::Disconnected(format!(
+                "session to {} is notfound",
+                node.p2p_address_with_node_id()
+            ))
+        })?;
+        let shared = self.shared.read().unwrap();
+        shared
+            .get_protocol_receiver(&session.id, &protocol.protocol_id())
+            .ok_or_else(|| Error::ProtocolNotOpen {
+                protocol: protocol.name(),
+                node: node.p2p_address_with_node_id(),
+            })
+    }
+
+    // Receive the relay messages until `extract` returns some item, and return the item
+    fn try_recv_relay<T, F>(
+        &self,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+        timeout: Duration,
+        mut extract: F,
+    ) -> Result<T, Error>
+    where
+        F: FnMut(packed::RelayMessageUnion) -> Option<T>,
+    {
+        assert_relay_protocol(relay_protocol);
+        let mut extracted = None;
+        self.try_recv_until(
+            node,
+            relay_protocol,
+            |message| {
+                extracted = message.as_relay().cloned().and_then(&mut extract);
+                extracted.is_some()
+            },
+            timeout,
+        )?;
+        extracted.ok_or_else(|| {
+            Error::Other(format!(
+                "no matched relay message received from node \"{}\"",
+                node.node_name()
+            ))
+        })
+    }
+}
+
+pub(super) fn assert_relay_protocol(relay_protocol: &SupportProtocols) {
+    assert!(
+        relay_protocol.protocol_id() == SupportProtocols::Relay.protocol_id()
+            || relay_protocol.protocol_id() == SupportProtocols::RelayV2.protocol_id()
+    );
 }
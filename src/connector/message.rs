@@ -1,6 +1,7 @@
 //! A set of functions used to construct network messages.
 use ckb_types::{
-    core::{Cycle, TransactionView},
+    bytes::Bytes,
+    core::{BlockNumber, BlockView, Cycle, HeaderView, TransactionView, UncleBlockView},
     packed,
     prelude::*,
 };
@@ -80,6 +81,124 @@ pub fn build_relay_transaction_hashes(hashes: Vec<packed::Byte32>) -> packed::Re
         .build()
 }
 
+/// Return the short id of `transaction` in compact blocks
+pub fn compact_block_short_id(transaction: &TransactionView) -> packed::ProposalShortId {
+    transaction.proposal_short_id()
+}
+
+/// Build the compact block of `block`. The cellbase and the transactions at
+/// `prefilled_indexes` are prefilled, the others are sent as short ids.
+pub fn build_relay_compact_block(
+    block: &BlockView,
+    prefilled_indexes: &[usize],
+) -> packed::RelayMessage {
+    let mut short_ids = Vec::new();
+    let mut prefilled_transactions = Vec::new();
+    for (index, transaction) in block.transactions().iter().enumerate() {
+        if index == 0 || prefilled_indexes.contains(&index) {
+            let prefilled_transaction = packed::IndexTransaction::new_builder()
+                .index((index as u32).pack())
+                .transaction(transaction.data())
+                .build();
+            prefilled_transactions.push(prefilled_transaction);
+        } else {
+            short_ids.push(compact_block_short_id(transaction));
+        }
+    }
+    let compact_block = packed::CompactBlock::new_builder()
+        .header(block.header().data())
+        .short_ids(short_ids.pack())
+        .prefilled_transactions(
+            packed::IndexTransactionVec::new_builder()
+                .set(prefilled_transactions)
+                .build(),
+        )
+        .uncles(block.uncle_hashes())
+        .proposals(block.data().proposals())
+        .build();
+    packed::RelayMessage::new_builder()
+        .set(compact_block)
+        .build()
+}
+
+pub fn build_relay_get_block_transactions(
+    block_hash: packed::Byte32,
+    indexes: Vec<u32>,
+    uncle_indexes: Vec<u32>,
+) -> packed::RelayMessage {
+    let get_block_transactions = packed::GetBlockTransactions::new_builder()
+        .block_hash(block_hash)
+        .indexes(indexes.pack())
+        .uncle_indexes(uncle_indexes.pack())
+        .build();
+    packed::RelayMessage::new_builder()
+        .set(get_block_transactions)
+        .build()
+}
+
+pub fn build_relay_block_transactions(
+    block_hash: packed::Byte32,
+    transactions: &[TransactionView],
+    uncles: &[UncleBlockView],
+) -> packed::RelayMessage {
+    let transactions = transactions
+        .iter()
+        .map(|transaction| transaction.data())
+        .collect::<Vec<_>>();
+    let uncles = uncles.iter().map(|uncle| uncle.data()).collect::<Vec<_>>();
+    let block_transactions = packed::BlockTransactions::new_builder()
+        .block_hash(block_hash)
+        .transactions(
+            packed::TransactionVec::new_builder()
+                .set(transactions)
+                .build(),
+        )
+        .uncles(packed::UncleBlockVec::new_builder().set(uncles).build())
+        .build();
+    packed::RelayMessage::new_builder()
+        .set(block_transactions)
+        .build()
+}
+
+pub fn build_relay_get_relay_transactions(tx_hashes: Vec<packed::Byte32>) -> packed::RelayMessage {
+    let get_relay_transactions = packed::GetRelayTransactions::new_builder()
+        .tx_hashes(tx_hashes.pack())
+        .build();
+    packed::RelayMessage::new_builder()
+        .set(get_relay_transactions)
+        .build()
+}
+
+pub fn build_relay_get_block_proposal(
+    block_hash: packed::Byte32,
+    proposals: Vec<packed::ProposalShortId>,
+) -> packed::RelayMessage {
+    let get_block_proposal = packed::GetBlockProposal::new_builder()
+        .block_hash(block_hash)
+        .proposals(proposals.pack())
+        .build();
+    packed::RelayMessage::new_builder()
+        .set(get_block_proposal)
+        .build()
+}
+
+pub fn build_relay_block_proposal(transactions: &[TransactionView]) -> packed::RelayMessage {
+    let transactions = transactions
+        .iter()
+        .map(|transaction| transaction.data())
+        .collect::<Vec<_>>();
+    let block_proposal = packed::BlockProposal::new_builder()
+        .transactions(
+            packed::TransactionVec::new_builder()
+                .set(transactions)
+                .build(),
+        )
+        .build();
+    packed::RelayMessage::new_builder()
+        .set(block_proposal)
+        .build()
+}
+
 pub fn build_discovery_get_nodes(
     listening_port: Option<u16>,
     max_nodes: u32,
@@ -135,3 +254,121 @@ pub fn build_discovery_nodes(
         .payload(discovery_payload)
         .build()
 }
+
+pub fn build_sync_get_headers(
+    block_locator_hashes: Vec<packed::Byte32>,
+    hash_stop: packed::Byte32,
+) -> packed::SyncMessage {
+    let get_headers = packed::GetHeaders::new_builder()
+        .block_locator_hashes(block_locator_hashes.pack())
+        .hash_stop(hash_stop)
+        .build();
+    packed::SyncMessage::new_builder().set(get_headers).build()
+}
+
+pub fn build_sync_headers(headers: &[HeaderView]) -> packed::SyncMessage {
+    let headers = headers
+        .iter()
+        .map(|header| header.data())
+        .collect::<Vec<_>>();
+    let send_headers = packed::SendHeaders::new_builder()
+        .headers(packed::HeaderVec::new_builder().set(headers).build())
+        .build();
+    packed::SyncMessage::new_builder().set(send_headers).build()
+}
+
+pub fn build_sync_get_blocks(block_hashes: Vec<packed::Byte32>) -> packed::SyncMessage {
+    let get_blocks = packed::GetBlocks::new_builder()
+        .block_hashes(block_hashes.pack())
+        .build();
+    packed::SyncMessage::new_builder().set(get_blocks).build()
+}
+
+pub fn build_sync_block(block: &BlockView) -> packed::SyncMessage {
+    let send_block = packed::SendBlock::new_builder().block(block.data()).build();
+    packed::SyncMessage::new_builder().set(send_block).build()
+}
+
+pub fn build_sync_in_ibd() -> packed::SyncMessage {
+    packed::SyncMessage::new_builder()
+        .set(packed::InIBD::new_builder().build())
+        .build()
+}
+
+pub fn build_filter_get_block_filters(start_number: BlockNumber) -> packed::BlockFilterMessage {
+    let get_block_filters = packed::GetBlockFilters::new_builder()
+        .start_number(start_number.pack())
+        .build();
+    packed::BlockFilterMessage::new_builder()
+        .set(get_block_filters)
+        .build()
+}
+
+pub fn build_filter_block_filters(
+    start_number: BlockNumber,
+    block_hashes: Vec<packed::Byte32>,
+    filters: Vec<Bytes>,
+) -> packed::BlockFilterMessage {
+    let filters = filters
+        .into_iter()
+        .map(|filter| filter.pack())
+        .collect::<Vec<_>>();
+    let block_filters = packed::BlockFilters::new_builder()
+        .start_number(start_number.pack())
+        .block_hashes(block_hashes.pack())
+        .filters(packed::BytesVec::new_builder().set(filters).build())
+        .build();
+    packed::BlockFilterMessage::new_builder()
+        .set(block_filters)
+        .build()
+}
+
+pub fn build_filter_get_block_filter_hashes(
+    start_number: BlockNumber,
+) -> packed::BlockFilterMessage {
+    let get_block_filter_hashes = packed::GetBlockFilterHashes::new_builder()
+        .start_number(start_number.pack())
+        .build();
+    packed::BlockFilterMessage::new_builder()
+        .set(get_block_filter_hashes)
+        .build()
+}
+
+pub fn build_filter_block_filter_hashes(
+    start_number: BlockNumber,
+    parent_block_filter_hash: packed::Byte32,
+    block_filter_hashes: Vec<packed::Byte32>,
+) -> packed::BlockFilterMessage {
+    let block_filter_hashes = packed::BlockFilterHashes::new_builder()
+        .start_number(start_number.pack())
+        .parent_block_filter_hash(parent_block_filter_hash)
+        .block_filter_hashes(block_filter_hashes.pack())
+        .build();
+    packed::BlockFilterMessage::new_builder()
+        .set(block_filter_hashes)
+        .build()
+}
+
+pub fn build_filter_get_block_filter_check_points(
+    start_number: BlockNumber,
+) -> packed::BlockFilterMessage {
+    let get_block_filter_check_points = packed::GetBlockFilterCheckPoints::new_builder()
+        .start_number(start_number.pack())
+        .build();
+    packed::BlockFilterMessage::new_builder()
+        .set(get_block_filter_check_points)
+        .build()
+}
+
+pub fn build_filter_block_filter_check_points(
+    start_number: BlockNumber,
+    block_filter_hashes: Vec<packed::Byte32>,
+) -> packed::BlockFilterMessage {
+    let block_filter_check_points = packed::BlockFilterCheckPoints::new_builder()
+        .start_number(start_number.pack())
+        .block_filter_hashes(block_filter_hashes.pack())
+        .build();
+    packed::BlockFilterMessage::new_builder()
+        .set(block_filter_check_points)
+        .build()
+}
@@ -0,0 +1,198 @@
+//! Deliberately protocol-violating traffic, used to test the ban scores of ckb.
+use super::extension::assert_relay_protocol;
+use super::message::{build_relay_compact_block, build_sync_block, build_sync_headers};
+use super::{Connector, SupportProtocols};
+use crate::util::{p2p_ip, wait_until};
+use crate::{Error, Node};
+use ckb_jsonrpc_types::BannedAddr;
+use ckb_types::{
+    bytes::Bytes,
+    core::{BlockView, Cycle, TransactionView},
+    packed,
+    prelude::*,
+};
+
+/// Bytes which are not a valid molecule message of any protocol: the union item id, i.e. the
+/// leading little-endian u32, is 0xffffffff, which does not exist, followed by 8 zero bytes.
+const UNDECODABLE_BYTES: [u8; 12] = [
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+impl Connector {
+    /// Send bytes which can not be decoded under `protocol`
+    pub fn send_undecodable_message(
+        &self,
+        node: &Node,
+        protocol: SupportProtocols,
+    ) -> Result<(), String> {
+        self.send(node, protocol, Bytes::from_static(&UNDECODABLE_BYTES))
+    }
+
+    /// Send a frame 1 byte longer than `protocol.max_frame_length()`. The frame is filled with
+    /// incompressible bytes, so it stays oversized on the wire.
+    ///
+    /// The protocol handler of the connector should be built with a larger
+    /// `SimpleProtocolHandler::max_frame_length`, otherwise the connector's own codec refuses
+    /// to send it.
+    pub fn send_oversized_frame(
+        &self,
+        node: &Node,
+        protocol: SupportProtocols,
+    ) -> Result<(), String> {
+        let length = protocol.max_frame_length() + 1;
+        // xorshift64, enough for incompressible bytes
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let data = (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+        self.send(node, protocol, Bytes::from(data))
+    }
+
+    /// Send the headers which are not continuous: the child of the node's tip with the number
+    /// of the tip plus 2
+    pub fn send_invalid_headers(&self, node: &Node) -> Result<(), String> {
+        let tip = node.try_get_tip_block().map_err(|err| err.to_string())?;
+        let header = tip
+            .header()
+            .as_advanced_builder()
+            .parent_hash(tip.hash())
+            .number((tip.number() + 2).pack())
+            .build();
+        let message = build_sync_headers(&[header]);
+        self.send(node, SupportProtocols::Sync, message.as_bytes())
+    }
+
+    /// Send `block` via `SendBlock` though the node did not request it via `GetBlocks`
+    pub fn send_unsolicited_block(&self, node: &Node, block: &BlockView) -> Result<(), String> {
+        let message = build_sync_block(block);
+        self.send(node, SupportProtocols::Sync, message.as_bytes())
+    }
+
+    /// Send the compact block of a block on top of the node's tip, whose transactions root
+    /// does not match its transactions. Return the invalid block.
+    pub fn send_invalid_block(
+        &self,
+        node: &Node,
+        relay_protocol: SupportProtocols,
+    ) -> Result<BlockView, String> {
+        assert_relay_protocol(&relay_protocol);
+        let template = node
+            .rpc_client()
+            .try_get_block_template(None, None, None)
+            .map_err(|err| err.to_string())?;
+        let block = packed::Block::from(template)
+            .into_view()
+            .as_advanced_builder()
+            .transactions_root(packed::Byte32::zero())
+            .build_unchecked();
+        let message = build_relay_compact_block(&block, &[]);
+        self.send(node, relay_protocol, message.as_bytes())?;
+        Ok(block)
+    }
+
+    /// Relay every transaction of `transactions` `times` times, declaring `cycles`
+    pub fn send_spam_transactions(
+        &self,
+        node: &Node,
+        relay_protocol: SupportProtocols,
+        transactions: &[TransactionView],
+        cycles: Cycle,
+        times: usize,
+    ) -> Result<(), String> {
+        for _ in 0..times {
+            for transaction in transactions {
+                self.send_relay_transaction(node, relay_protocol.clone(), transaction, cycles)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait until `node` bans the connector, then return the ban entry, whose `ban_reason`
+    /// tells why
+    pub fn wait_for_ban(&self, node: &Node, timeout_secs: u64) -> Result<BannedAddr, String> {
+        self.try_wait_for_ban(node, timeout_secs)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn try_wait_for_ban(&self, node: &Node, timeout_secs: u64) -> Result<BannedAddr, Error> {
+        let connector_ip = self.try_connector_ip(node)?;
+        let mut rpc_error = None;
+        let mut banned = None;
+        wait_until(timeout_secs, || {
+            match node.rpc_client().try_get_banned_addresses() {
+                Ok(banned_addresses) => {
+                    banned = banned_addresses.into_iter().find(|banned_address| {
+                        banned_address.address.split('/').next() == Some(connector_ip.as_str())
+                    });
+                    banned.is_some()
+                }
+                Err(err) => {
+                    rpc_error = Some(err);
+                    true
+                }
+            }
+        });
+        if let Some(err) = rpc_error {
+            return Err(err);
+        }
+        match banned {
+            Some(banned) => {
+                crate::info!(
+                    "Connector is banned by node \"{}\", address: {}, ban_reason: {}",
+                    node.node_name(),
+                    banned.address,
+                    banned.ban_reason
+                );
+                Ok(banned)
+            }
+            None => Err(Error::Timeout(format!(
+                "waiting for node \"{}\" to ban the connector of IP {}, banned addresses: {:?}",
+                node.node_name(),
+                connector_ip,
+                node.rpc_client().try_get_banned_addresses()?
+            ))),
+        }
+    }
+
+    // Return the IP of the connector seen by `node`. If the connector is already disconnected
+    // from a local node, they share the loopback IP.
+    fn try_connector_ip(&self, node: &Node) -> Result<String, Error> {
+        let peer_id = self.key_pair().peer_id().to_base58();
+        let peer = node
+            .rpc_client()
+            .try_get_peers()?
+            .into_iter()
+            .find(|peer| peer.node_id == peer_id);
+        let address = match peer {
+            Some(peer) => peer
+                .addresses
+                .first()
+                .map(|address| address.address.clone()),
+            None if !node.is_remote() => Some(node.p2p_address()),
+            None => None,
+        };
+        address.as_deref().and_then(p2p_ip).ok_or_else(|| {
+            Error::Disconnected(format!(
+                "the connector is not connected to node \"{}\"",
+                node.node_name()
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UNDECODABLE_BYTES;
+    use ckb_types::{packed, prelude::*};
+
+    #[test]
+    fn test_undecodable_bytes() {
+        assert!(packed::SyncMessage::from_slice(&UNDECODABLE_BYTES).is_err());
+        assert!(packed::RelayMessage::from_slice(&UNDECODABLE_BYTES).is_err());
+    }
+}
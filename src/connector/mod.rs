@@ -1,18 +1,23 @@
 mod compress;
 mod extension;
 pub mod message;
+mod misbehavior;
+mod received;
+mod responder;
 mod shared;
 mod simple_protocol_handler;
 mod simple_service_handler;
 mod support_protocols;
 
 pub use compress::{compress, decompress};
+pub use received::ReceivedMessage;
+pub use responder::Responder;
 pub use shared::SharedState;
 pub use simple_protocol_handler::SimpleProtocolHandler;
 pub use simple_service_handler::SimpleServiceHandler;
 pub use support_protocols::SupportProtocols;
 
-use crate::Node;
+use crate::{Error, Node};
 use ckb_stop_handler::{SignalSender, StopHandler};
 use futures::prelude::*;
 use tentacle::{
@@ -193,6 +198,11 @@ impl ConnectorBuilder {
 impl Connector {
     /// Try to establish connection with `node`. This function blocks until all protocols opened.
     pub fn connect(&mut self, node: &Node) -> Result<(), String> {
+        self.try_connect(node).map_err(|err| err.to_string())
+    }
+
+    /// Try to establish connection with `node`. This function blocks until all protocols opened.
+    pub fn try_connect(&mut self, node: &Node) -> Result<(), Error> {
         // Open all protocols connection to target node
         let node_addr = node.p2p_address_with_node_id().parse().unwrap();
         crate::info!(
@@ -201,7 +211,7 @@ impl Connector {
         );
         self.p2p_service_controller
             .dial(node_addr, P2PTargetProtocol::All)
-            .map_err(|err| format!("Connector dial error: {:?}", err))?;
+            .map_err(|err| Error::P2P(format!("Connector dial error: {:?}", err)))?;
 
         // Wait for all protocols connections establishment
         let start_time = Instant::now();
@@ -242,29 +252,40 @@ impl Connector {
             }
         }
 
-        Err(format!(
-            "Connector is timeout when connecting to {}",
+        Err(Error::Timeout(format!(
+            "Connector connecting to {}",
             node.node_name()
-        ))
+        )))
     }
 
     /// Send `data` through the protocol of the session
     pub fn send(&self, node: &Node, protocol: SupportProtocols, data: Bytes) -> Result<(), String> {
+        self.try_send(node, protocol, data)
+            .map_err(|err| err.to_string())
+    }
+
+    /// Send `data` through the protocol of the session
+    pub fn try_send(
+        &self,
+        node: &Node,
+        protocol: SupportProtocols,
+        data: Bytes,
+    ) -> Result<(), Error> {
         let session = self.get_session(node).ok_or_else(|| {
-            format!(
+            Error::Disconnected(format!(
                 "The connection was disconnected to \"{}\"",
                 node.node_name()
-            )
+            ))
         })?;
         self.p2p_service_controller
             .send_message_to(session.id, protocol.protocol_id(), data)
             .map_err(|err| {
-                format!(
+                Error::P2P(format!(
                     "Connector send message under protocol \"{}\" to \"{}\", error: {:?}",
                     protocol.name(),
                     node.node_name(),
                     err
-                )
+                ))
             })
     }
 
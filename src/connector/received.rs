@@ -0,0 +1,230 @@
+use super::{Connector, SupportProtocols};
+use crate::{Error, Node};
+use ckb_types::{bytes::Bytes, packed, prelude::*};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A message received by `Connector`, decoded according to its protocol
+#[derive(Debug, Clone)]
+pub enum ReceivedMessage {
+    Sync(packed::SyncMessageUnion),
+    /// Received under `SupportProtocols::Relay` or `SupportProtocols::RelayV2`
+    Relay(packed::RelayMessageUnion),
+    Discovery(packed::DiscoveryPayloadUnion),
+    Identify(packed::IdentifyMessage),
+    Ping(packed::PingPayloadUnion),
+    Filter(packed::BlockFilterMessageUnion),
+    /// Received under the protocols without typed decoding, e.g. `SupportProtocols::Time`
+    Raw(Bytes),
+}
+
+impl ReceivedMessage {
+    /// Decode `data` received under `protocol`
+    pub fn decode(protocol: &SupportProtocols, data: Bytes) -> Result<Self, Error> {
+        let message = match protocol {
+            SupportProtocols::Sync => {
+                let message = packed::SyncMessage::from_compatible_slice(&data)
+                    .map_err(|err| malformed(protocol, err))?;
+                ReceivedMessage::Sync(message.to_enum())
+            }
+            SupportProtocols::Relay | SupportProtocols::RelayV2 => {
+                let message = packed::RelayMessage::from_compatible_slice(&data)
+                    .map_err(|err| malformed(protocol, err))?;
+                ReceivedMessage::Relay(message.to_enum())
+            }
+            SupportProtocols::Discovery => {
+                let message = packed::DiscoveryMessage::from_slice(&data)
+                    .map_err(|err| malformed(protocol, err))?;
+                ReceivedMessage::Discovery(message.payload().to_enum())
+            }
+            SupportProtocols::Identify => {
+                let message = packed::IdentifyMessage::from_slice(&data)
+                    .map_err(|err| malformed(protocol, err))?;
+                ReceivedMessage::Identify(message)
+            }
+            SupportProtocols::Ping => {
+                let message = packed::PingMessage::from_slice(&data)
+                    .map_err(|err| malformed(protocol, err))?;
+                ReceivedMessage::Ping(message.payload().to_enum())
+            }
+            SupportProtocols::Filter => {
+                let message = packed::BlockFilterMessage::from_compatible_slice(&data)
+                    .map_err(|err| malformed(protocol, err))?;
+                ReceivedMessage::Filter(message.to_enum())
+            }
+            _ => ReceivedMessage::Raw(data),
+        };
+        Ok(message)
+    }
+
+    pub fn as_sync(&self) -> Option<&packed::SyncMessageUnion> {
+        match self {
+            ReceivedMessage::Sync(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    pub fn as_relay(&self) -> Option<&packed::RelayMessageUnion> {
+        match self {
+            ReceivedMessage::Relay(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    pub fn as_discovery(&self) -> Option<&packed::DiscoveryPayloadUnion> {
+        match self {
+            ReceivedMessage::Discovery(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    pub fn as_identify(&self) -> Option<&packed::IdentifyMessage> {
+        match self {
+            ReceivedMessage::Identify(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    pub fn as_ping(&self) -> Option<&packed::PingPayloadUnion> {
+        match self {
+            ReceivedMessage::Ping(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    pub fn as_filter(&self) -> Option<&packed::BlockFilterMessageUnion> {
+        match self {
+            ReceivedMessage::Filter(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    /// The name of the message item, e.g. "SendHeaders", used for logging
+    pub fn item_name(&self) -> &str {
+        match self {
+            ReceivedMessage::Sync(message) => message.item_name(),
+            ReceivedMessage::Relay(message) => message.item_name(),
+            ReceivedMessage::Discovery(message) => message.item_name(),
+            ReceivedMessage::Identify(_) => "IdentifyMessage",
+            ReceivedMessage::Ping(message) => message.item_name(),
+            ReceivedMessage::Filter(message) => message.item_name(),
+            ReceivedMessage::Raw(_) => "Raw",
+        }
+    }
+}
+
+impl Connector {
+    /// Receive the next message under `protocol` and decode it
+    pub fn recv_message(
+        &self,
+        node: &Node,
+        protocol: &SupportProtocols,
+    ) -> Result<ReceivedMessage, String> {
+        self.try_recv_message(node, protocol)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn try_recv_message(
+        &self,
+        node: &Node,
+        protocol: &SupportProtocols,
+    ) -> Result<ReceivedMessage, Error> {
+        let data = self.try_recv(node, protocol)?;
+        ReceivedMessage::decode(protocol, data)
+    }
+
+    pub fn recv_message_timeout(
+        &self,
+        timeout: Duration,
+        node: &Node,
+        protocol: &SupportProtocols,
+    ) -> Result<ReceivedMessage, String> {
+        self.try_recv_message_timeout(timeout, node, protocol)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn try_recv_message_timeout(
+        &self,
+        timeout: Duration,
+        node: &Node,
+        protocol: &SupportProtocols,
+    ) -> Result<ReceivedMessage, Error> {
+        let data = self.try_recv_timeout(timeout, node, protocol)?;
+        ReceivedMessage::decode(protocol, data)
+    }
+
+    /// Receive the messages under `protocol` until one matches `predicate`, and return it. The
+    /// unmatched messages, e.g. the periodic `GetHeaders`, are dropped.
+    ///
+    /// ```ignore
+    /// let message = connector.recv_until(
+    ///     &node,
+    ///     &SupportProtocols::Sync,
+    ///     |message| matches!(message.as_sync(), Some(SyncMessageUnion::SendHeaders(_))),
+    ///     Duration::from_secs(10),
+    /// )?;
+    /// ```
+    pub fn recv_until<P>(
+        &self,
+        node: &Node,
+        protocol: &SupportProtocols,
+        predicate: P,
+        timeout: Duration,
+    ) -> Result<ReceivedMessage, String>
+    where
+        P: FnMut(&ReceivedMessage) -> bool,
+    {
+        self.try_recv_until(node, protocol, predicate, timeout)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn try_recv_until<P>(
+        &self,
+        node: &Node,
+        protocol: &SupportProtocols,
+        mut predicate: P,
+        timeout: Duration,
+    ) -> Result<ReceivedMessage, Error>
+    where
+        P: FnMut(&ReceivedMessage) -> bool,
+    {
+        let start_time = Instant::now();
+        let mut skipped = Vec::new();
+        loop {
+            let remaining = timeout
+                .checked_sub(start_time.elapsed())
+                .unwrap_or_default();
+            let data = match self.try_recv_timeout(remaining, node, protocol) {
+                Ok(data) => data,
+                Err(Error::Timeout(_)) => {
+                    return Err(Error::Timeout(format!(
+                        "receive the expected message under protocol \"{}\" from {}, skipped: {:?}",
+                        protocol.name(),
+                        node.node_name(),
+                        skipped
+                    )));
+                }
+                Err(err) => return Err(err),
+            };
+            let message = ReceivedMessage::decode(protocol, data)?;
+            if predicate(&message) {
+                return Ok(message);
+            }
+            crate::trace!(
+                "Connector skips message {} under protocol \"{}\" from {}",
+                message.item_name(),
+                protocol.name(),
+                node.node_name()
+            );
+            skipped.push(message.item_name().to_string());
+        }
+    }
+}
+
+fn malformed<E: fmt::Display>(protocol: &SupportProtocols, err: E) -> Error {
+    Error::Other(format!(
+        "malformed message under protocol \"{}\", error: {}",
+        protocol.name(),
+        err
+    ))
+}
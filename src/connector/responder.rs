@@ -0,0 +1,207 @@
+use super::message::{build_identify_message, build_sync_block, build_sync_headers};
+use super::{ReceivedMessage, SupportProtocols};
+use ckb_types::{
+    core::{BlockView, HeaderView},
+    packed,
+    prelude::*,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tentacle::{bytes::Bytes, context::ProtocolContextMutRef};
+
+/// The max number of headers replied for one `GetHeaders`, the same as ckb
+const MAX_HEADERS_LEN: usize = 2_000;
+
+/// Automatic replies of `SimpleProtocolHandler`, so that the connector behaves like a real
+/// peer and is not evicted or stalled by ckb. The received messages are still mirrored into
+/// the mailbox.
+#[derive(Clone, Debug)]
+pub enum Responder {
+    /// `SupportProtocols::Ping`: reply `Pong` to `Ping`
+    Ping,
+    /// `SupportProtocols::Identify`: send the identify message once the protocol opens
+    Identify {
+        network_identifier: String,
+        client_version: String,
+    },
+    /// `SupportProtocols::Time`: send the local time once the protocol opens. ckb only sends it
+    /// to the inbound peers, but the connector always dials out, so it is sent on the outbound
+    /// sessions as well.
+    Time,
+    /// `SupportProtocols::Sync`: reply `GetHeaders` and `GetBlocks` from `chain`, which
+    /// starts with the genesis block
+    Sync { chain: Vec<BlockView> },
+}
+
+impl Responder {
+    /// The protocol this responder works on
+    pub fn protocol(&self) -> SupportProtocols {
+        match self {
+            Responder::Ping => SupportProtocols::Ping,
+            Responder::Identify { .. } => SupportProtocols::Identify,
+            Responder::Time => SupportProtocols::Time,
+            Responder::Sync { .. } => SupportProtocols::Sync,
+        }
+    }
+
+    pub(super) fn on_connected(&self, context: &ProtocolContextMutRef) {
+        match self {
+            Responder::Identify {
+                network_identifier,
+                client_version,
+            } => {
+                let message = build_identify_message(
+                    network_identifier,
+                    client_version,
+                    Vec::new(),
+                    context.session.address.clone(),
+                );
+                self.reply(context, message.as_bytes());
+            }
+            Responder::Time => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .expect("system time after unix epoch")
+                    .as_millis() as u64;
+                let message = packed::TimeMessage::new_builder()
+                    .payload(packed::Time::new_builder().timestamp(now.pack()).build())
+                    .build();
+                self.reply(context, message.as_bytes());
+            }
+            _ => {}
+        }
+    }
+
+    pub(super) fn on_received(&self, context: &ProtocolContextMutRef, message: &ReceivedMessage) {
+        match (self, message) {
+            (Responder::Ping, ReceivedMessage::Ping(packed::PingPayloadUnion::Ping(ping))) => {
+                let pong = packed::Pong::new_builder().nonce(ping.nonce()).build();
+                let message = packed::PingMessage::new_builder()
+                    .payload(packed::PingPayload::new_builder().set(pong).build())
+                    .build();
+                self.reply(context, message.as_bytes());
+            }
+            (
+                Responder::Sync { chain },
+                ReceivedMessage::Sync(packed::SyncMessageUnion::GetHeaders(get_headers)),
+            ) => {
+                let headers = headers_after_locator(chain, get_headers);
+                self.reply(context, build_sync_headers(&headers).as_bytes());
+            }
+            (
+                Responder::Sync { chain },
+                ReceivedMessage::Sync(packed::SyncMessageUnion::GetBlocks(get_blocks)),
+            ) => {
+                for hash in get_blocks.block_hashes().into_iter() {
+                    if let Some(block) = chain.iter().find(|block| block.hash() == hash) {
+                        self.reply(context, build_sync_block(block).as_bytes());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn reply(&self, context: &ProtocolContextMutRef, data: Bytes) {
+        if let Err(err) = context.send_message(data) {
+            crate::debug!(
+                "Responder failed to reply under protocol \"{}\", session: {:?}, error: {:?}",
+                self.protocol().name(),
+                context.session,
+                err
+            );
+        }
+    }
+}
+
+// Return the headers after the first known locator hash, up to `hash_stop` or
+// `MAX_HEADERS_LEN` headers
+fn headers_after_locator(chain: &[BlockView], get_headers: &packed::GetHeaders) -> Vec<HeaderView> {
+    let hash_stop = get_headers.hash_stop();
+    let start = get_headers
+        .block_locator_hashes()
+        .into_iter()
+        .find_map(|hash| chain.iter().position(|block| block.hash() == hash));
+    let start = match start {
+        Some(index) => index + 1,
+        None => return Vec::new(),
+    };
+    let mut headers = Vec::new();
+    for block in chain.iter().skip(start).take(MAX_HEADERS_LEN) {
+        headers.push(block.header());
+        if block.hash() == hash_stop {
+            break;
+        }
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{headers_after_locator, MAX_HEADERS_LEN};
+    use ckb_types::{
+        core::{BlockBuilder, BlockView},
+        packed,
+        prelude::*,
+    };
+
+    fn build_chain(length: usize) -> Vec<BlockView> {
+        let mut chain = vec![BlockBuilder::default().build()];
+        while chain.len() < length {
+            let parent = chain.last().unwrap();
+            let block = BlockBuilder::default()
+                .parent_hash(parent.hash())
+                .number((parent.number() + 1).pack())
+                .build();
+            chain.push(block);
+        }
+        chain
+    }
+
+    fn get_headers(locator: &[&BlockView], hash_stop: packed::Byte32) -> packed::GetHeaders {
+        let locator = locator.iter().map(|block| block.hash()).collect::<Vec<_>>();
+        packed::GetHeaders::new_builder()
+            .block_locator_hashes(locator.pack())
+            .hash_stop(hash_stop)
+            .build()
+    }
+
+    fn numbers(chain: &[BlockView], get_headers: &packed::GetHeaders) -> Vec<u64> {
+        headers_after_locator(chain, get_headers)
+            .iter()
+            .map(|header| header.number())
+            .collect()
+    }
+
+    #[test]
+    fn test_headers_after_locator_not_found() {
+        let chain = build_chain(10);
+        let unknown = build_chain(12).pop().unwrap();
+        let get_headers = get_headers(&[&unknown], packed::Byte32::zero());
+        assert!(headers_after_locator(&chain, &get_headers).is_empty());
+    }
+
+    #[test]
+    fn test_headers_after_first_known_locator() {
+        let chain = build_chain(10);
+        let unknown = build_chain(12).pop().unwrap();
+        let get_headers = get_headers(&[&unknown, &chain[6], &chain[2]], packed::Byte32::zero());
+        assert_eq!(numbers(&chain, &get_headers), vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn test_headers_after_locator_until_hash_stop() {
+        let chain = build_chain(10);
+        let get_headers = get_headers(&[&chain[2]], chain[5].hash());
+        assert_eq!(numbers(&chain, &get_headers), vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn test_headers_after_locator_capped() {
+        let chain = build_chain(MAX_HEADERS_LEN + 10);
+        let get_headers = get_headers(&[&chain[0]], packed::Byte32::zero());
+        let header_numbers = numbers(&chain, &get_headers);
+        assert_eq!(header_numbers.len(), MAX_HEADERS_LEN);
+        assert_eq!(header_numbers.first(), Some(&1));
+        assert_eq!(header_numbers.last(), Some(&(MAX_HEADERS_LEN as u64)));
+    }
+}
@@ -1,6 +1,7 @@
 use super::compress::{compress, decompress};
 use super::SharedState;
 use super::SupportProtocols;
+use super::{ReceivedMessage, Responder};
 use tentacle::{
     builder::MetaBuilder as P2PMetaBuilder,
     bytes,
@@ -9,21 +10,59 @@ use tentacle::{
     traits::ServiceProtocol as P2PServiceProtocol,
 };
 use std::sync::{Arc, RwLock};
+use tokio_util::codec::length_delimited;
 
 /// Simple protocol handler which implements tentacle's
 /// [`P2PServiceProtocol`](https://github.com/nervosnetwork/tentacle/blob/master/tentacle/src/traits.rs#L57-L77)
 pub struct SimpleProtocolHandler {
     shared: Arc<RwLock<SharedState>>,
     protocol: SupportProtocols,
+    responder: Option<Responder>,
+    max_frame_length: Option<usize>,
 }
 
 impl SimpleProtocolHandler {
     pub fn new(shared: Arc<RwLock<SharedState>>, protocol: SupportProtocols) -> Self {
-        Self { shared, protocol }
+        Self {
+            shared,
+            protocol,
+            responder: None,
+            max_frame_length: None,
+        }
+    }
+
+    /// Reply the messages automatically by `responder`, which should work on the protocol of
+    /// the handler
+    pub fn responder(mut self, responder: Responder) -> Self {
+        assert_eq!(
+            responder.protocol().protocol_id(),
+            self.protocol.protocol_id(),
+            "responder {:?} does not work on protocol \"{}\"",
+            responder,
+            self.protocol.name()
+        );
+        self.responder = Some(responder);
+        self
+    }
+
+    /// Override `SupportProtocols::max_frame_length` of the codec, e.g. to send the frames
+    /// which the node refuses
+    pub fn max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = Some(max_frame_length);
+        self
     }
 
     pub fn build(self, be_compressed: bool) -> P2PProtocolMeta {
-        let meta_builder: P2PMetaBuilder = self.protocol.clone().into();
+        let mut meta_builder: P2PMetaBuilder = self.protocol.clone().into();
+        if let Some(max_frame_length) = self.max_frame_length {
+            meta_builder = meta_builder.codec(move || {
+                Box::new(
+                    length_delimited::Builder::new()
+                        .max_frame_length(max_frame_length)
+                        .new_codec(),
+                )
+            });
+        }
         if be_compressed {
             meta_builder
                 .before_send(compress)
@@ -50,6 +89,9 @@ impl P2PServiceProtocol for SimpleProtocolHandler {
         if let Ok(mut shared) = self.shared.write() {
             shared.add_protocol(context.session, context.proto_id);
         }
+        if let Some(ref responder) = self.responder {
+            responder.on_connected(&context);
+        }
     }
 
     fn disconnected(&mut self, context: ProtocolContextMutRef) {
@@ -75,7 +117,17 @@ impl P2PServiceProtocol for SimpleProtocolHandler {
                 .unwrap_or_else(|| {
                     panic!("received message but shared.get_protocol_sender returns None")
                 });
-            let _ = sender.send(data);
+            let _ = sender.send(data.clone());
+        }
+        if let Some(ref responder) = self.responder {
+            match ReceivedMessage::decode(&self.protocol, data) {
+                Ok(message) => responder.on_received(&context, &message),
+                Err(err) => crate::debug!(
+                    "SimpleProtocolHandler does not reply the message, protocol: {}, error: {}",
+                    self.protocol.name(),
+                    err
+                ),
+            }
         }
     }
 }
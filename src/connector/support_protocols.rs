@@ -49,6 +49,10 @@ pub enum SupportProtocols {
     /// Alert: A protocol reserved by the Nervos Foundation to publish network-wide announcements.
     /// Any information sent from the protocol is verified by multi-signature
     Alert,
+    /// Filter: serves the compact block filters, mainly to light clients.
+    ///
+    /// [RFC](https://github.com/nervosnetwork/rfcs/blob/master/rfcs/0045-client-block-filter/0045-client-block-filter.md)
+    Filter,
 }
 
 impl SupportProtocols {
@@ -65,6 +69,7 @@ impl SupportProtocols {
             SupportProtocols::Time => 102,
             SupportProtocols::RelayV2 => 103,
             SupportProtocols::Alert => 110,
+            SupportProtocols::Filter => 121,
         }
         .into()
     }
@@ -82,6 +87,7 @@ impl SupportProtocols {
             SupportProtocols::RelayV2 => "/ckb/relay",
             SupportProtocols::Time => "/ckb/tim",
             SupportProtocols::Alert => "/ckb/alt",
+            SupportProtocols::Filter => "/ckb/filter",
         }
         .to_owned()
     }
@@ -102,6 +108,7 @@ impl SupportProtocols {
             SupportProtocols::Time => vec!["1".to_owned(), "2".to_owned()],
             SupportProtocols::Alert => vec!["1".to_owned(), "2".to_owned()],
             SupportProtocols::RelayV2 => vec!["2".to_owned()],
+            SupportProtocols::Filter => vec!["2".to_owned()],
         }
     }
 
@@ -117,6 +124,7 @@ impl SupportProtocols {
             SupportProtocols::Relay | SupportProtocols::RelayV2 => 4 * 1024 * 1024, // 4   MB
             SupportProtocols::Time => 1024,              // 1   KB
             SupportProtocols::Alert => 128 * 1024,       // 128 KB
+            SupportProtocols::Filter => 2 * 1024 * 1024, // 2   MB
         }
     }
 
@@ -134,7 +142,10 @@ impl SupportProtocols {
                 no_blocking_flag.disable_all();
                 no_blocking_flag
             }
-            SupportProtocols::Sync | SupportProtocols::Relay | SupportProtocols::RelayV2 => {
+            SupportProtocols::Sync
+            | SupportProtocols::Relay
+            | SupportProtocols::RelayV2
+            | SupportProtocols::Filter => {
                 let mut blocking_recv_flag = BlockingFlag::default();
                 blocking_recv_flag.disable_connected();
                 blocking_recv_flag.disable_disconnected();
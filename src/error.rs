@@ -0,0 +1,124 @@
+use crate::rpc::RpcError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+/// Errors returned by the fallible `try_*` APIs of `Node`, `Nodes`, `RpcClient` and `Connector`.
+#[derive(Debug)]
+pub enum Error {
+    /// The node rejected the request with a JSON-RPC error object
+    Rpc(RpcError),
+    /// Failed to transport the HTTP request or to read the response
+    Transport(reqwest::Error),
+    /// Failed to serialize the request parameters or deserialize the result
+    Json(serde_json::Error),
+    /// I/O error, e.g. spawning the ckb process or preparing the working directory
+    Io(io::Error),
+    /// Waiting for a condition did not succeed within the given time
+    Timeout(String),
+    /// The ckb process exited unexpectedly
+    NodeCrashed {
+        node_name: String,
+        status: ExitStatus,
+        log_path: PathBuf,
+    },
+    /// The requested item does not exist on the node, e.g. a block of an unknown number
+    NotFound(String),
+    /// There is no session between the connector and the node
+    Disconnected(String),
+    /// The session exists but the protocol is not opened
+    ProtocolNotOpen { protocol: String, node: String },
+    /// Error reported by the p2p service, e.g. dialing or sending failure
+    P2P(String),
+    /// Any other error
+    Other(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Rpc(err) => write!(f, "rpc error: {}", err),
+            Error::Transport(err) => write!(f, "transport error: {}", err),
+            Error::Json(err) => write!(f, "json error: {}", err),
+            Error::Io(err) => write!(f, "io error: {}", err),
+            Error::Timeout(what) => write!(f, "timeout: {}", what),
+            Error::NodeCrashed {
+                node_name,
+                status,
+                log_path,
+            } => write!(
+                f,
+                "node \"{}\" crashed, {}, log_path: {}",
+                node_name,
+                status,
+                log_path.display()
+            ),
+            Error::NotFound(what) => write!(f, "not found: {}", what),
+            Error::Disconnected(what) => write!(f, "disconnected: {}", what),
+            Error::ProtocolNotOpen { protocol, node } => {
+                write!(f, "protocol \"{}\" to {} is not open", protocol, node)
+            }
+            Error::P2P(what) => write!(f, "p2p error: {}", what),
+            Error::Other(what) => write!(f, "{}", what),
+        }
+    }
+}
+
+impl ::std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
+        match self {
+            Error::Rpc(err) => Some(err),
+            Error::Transport(err) => Some(err),
+            Error::Json(err) => Some(err),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// Return the JSON-RPC error object if the node rejected the request
+    pub fn as_rpc_error(&self) -> Option<&RpcError> {
+        match self {
+            Error::Rpc(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            Error::Timeout(_) => true,
+            Error::Transport(err) => err.is_timeout(),
+            _ => false,
+        }
+    }
+
+    pub fn is_node_crashed(&self) -> bool {
+        matches!(self, Error::NodeCrashed { .. })
+    }
+}
+
+impl From<RpcError> for Error {
+    fn from(err: RpcError) -> Self {
+        Error::Rpc(err)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Transport(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
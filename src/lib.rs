@@ -1,17 +1,30 @@
 pub mod connector;
+mod error;
 pub mod logger;
 mod node;
 mod nodes;
 mod rpc;
 #[cfg(feature = "with_subscribe")]
 mod subscribe;
+mod tx_builder;
 mod user;
 pub mod util;
 
-pub use connector::{compress, decompress, Connector, ConnectorBuilder, SupportProtocols};
+pub use connector::{
+    compress, decompress, Connector, ConnectorBuilder, ReceivedMessage, Responder, SupportProtocols,
+};
+pub use error::Error;
 pub use logger::LOG_TARGET;
-pub use node::{BuildInstruction, Node, NodeOptions};
-pub use nodes::Nodes;
+pub use node::{
+    always_success_lock_script, minimal_unlock_point, AppConfigBuilder, BuildInstruction,
+    ChainSpecBuilder, DaoField, MiningBackend, Node, NodeOptions, OutputMode, VirtualClock,
+    CHAIN_SPEC_FILE, MEDIAN_TIME_BLOCK_COUNT, POST_MORTEM_DIR_ENV, TYPE_ID_CODE_HASH,
+};
+pub use node::{recorded_snapshot_hash, snapshot_content_hash, SNAPSHOT_HASH_FILE};
+pub use nodes::{Branch, ForkReport, ForkSide, Nodes, PARTITION_BAN_REASON};
+pub use rpc::{AsyncRpcClient, CkbVersion, RpcError};
+pub use tx_builder::{LockKind, TxBuilder, DEFAULT_FEE_RATE};
+pub use user::multisig::{MultisigConfig, MultisigUser};
 pub use user::User;
 
 pub use ckb_crypto;
@@ -22,9 +35,15 @@ pub use tentacle;
 use ckb_types::{h256, H256};
 
 pub const SYSTEM_CELL_ALWAYS_SUCCESS_INDEX: u32 = 5;
+pub const SYSTEM_CELL_DAO_INDEX: u32 = 2;
 pub const GENESIS_DEP_GROUP_TRANSACTION_INDEX: usize = 1;
 pub const GENESIS_SIGHASH_ALL_DEP_GROUP_CELL_INDEX: usize = 0;
+pub const GENESIS_MULTISIG_DEP_GROUP_CELL_INDEX: usize = 1;
 pub const SIGHASH_ALL_TYPE_HASH: H256 =
     h256!("0x9bd7e06f3ecf4be0f2fcd2188b23f1b9fcc88e5d4b65a8637b17723bbda3cce8");
 pub const SIGHASH_ALL_DATA_HASH: H256 =
     h256!("0x709f3fda12f561cfacf92273c57a98fede188a3f1a59b1f888d113f9cce08649");
+pub const MULTISIG_TYPE_HASH: H256 =
+    h256!("0x5c5069eb0857efc65e1bca0c07df34c31663b3622fd3876c876320fc9634e2a8");
+/// The lock period of NervosDAO withdrawing, in epochs
+pub const DAO_LOCK_PERIOD_EPOCHS: u64 = 180;
@@ -48,4 +48,10 @@ impl Node {
             .cell_dep(self.always_success_cell_dep())
             .build()
     }
+
+    /// Return the spendable cells locked by `always_success_script`, e.g. the mature cellbase
+    /// cells mined by this node. The node must run with `NodeOptions::indexer(true)`.
+    pub fn get_spendable_always_success_cells(&self) -> Vec<CellMeta> {
+        self.get_spendable_cells_by_lock_script(&self.always_success_script())
+    }
 }
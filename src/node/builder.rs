@@ -1,7 +1,8 @@
+use crate::rpc::batch::BATCH_CHUNK_SIZE;
 use crate::{Node, NodeOptions};
 use ckb_jsonrpc_types::TransactionTemplate;
 use ckb_types::{
-    core::{BlockNumber, TransactionView},
+    core::{BlockNumber, BlockView, TransactionView},
     packed::{self, ProposalShortId},
     prelude::*,
 };
@@ -174,8 +175,7 @@ impl Node {
                 node_name: String::from(node_name),
                 ..self.node_options().clone()
             };
-            let is_ckb2021 = self.rpc_client().ckb2021;
-            Node::init("cloned_node", node_options, is_ckb2021)
+            Node::init("cloned_node", node_options)
         };
         target_node.start();
 
@@ -190,22 +190,54 @@ impl Node {
         assert!(self.get_tip_block_number() <= source_node.get_tip_block_number());
         let min_tip_number = self.get_tip_block_number();
         let max_tip_number = source_node.get_tip_block_number();
-        let mut fixed_number = min_tip_number;
 
-        for number in (0..=min_tip_number).rev() {
-            if self.rpc_client().get_block_hash(number)
-                == source_node.rpc_client().get_block_hash(number)
-            {
-                fixed_number = number;
-                break;
+        // Scan backward from the tip, so that a short fork costs a few batch requests only
+        let mut end_number = min_tip_number;
+        let fixed_number = loop {
+            let start_number = end_number.saturating_sub(BATCH_CHUNK_SIZE as BlockNumber - 1);
+            let self_hashes = self
+                .rpc_client()
+                .try_get_block_hashes(start_number..=end_number)
+                .map_err(|err| err.to_string())?;
+            let source_hashes = source_node
+                .rpc_client()
+                .try_get_block_hashes(start_number..=end_number)
+                .map_err(|err| err.to_string())?;
+            let matched = self_hashes.iter().zip(source_hashes.iter()).rposition(
+                |(self_hash, source_hash)| self_hash.is_some() && self_hash == source_hash,
+            );
+            if let Some(offset) = matched {
+                break start_number + offset as BlockNumber;
             }
-        }
+            if start_number == 0 {
+                return Err(format!(
+                    "node \"{}\" and node \"{}\" have different genesis blocks",
+                    self.node_name(),
+                    source_node.node_name()
+                ));
+            }
+            end_number = start_number - 1;
+        };
 
-        for number in fixed_number + 1..=max_tip_number {
-            let block = source_node.get_block_by_number(number);
-            self.rpc_client()
-                .submit_block(block.number().to_string(), block.data().into())
+        let mut start_number = fixed_number + 1;
+        while start_number <= max_tip_number {
+            let end_number = ::std::cmp::min(
+                start_number + BATCH_CHUNK_SIZE as BlockNumber - 1,
+                max_tip_number,
+            );
+            let blocks = source_node
+                .rpc_client()
+                .try_get_blocks_by_number(start_number..=end_number)
                 .map_err(|err| err.to_string())?;
+            for (number, block) in (start_number..=end_number).zip(blocks) {
+                let block: BlockView = block
+                    .ok_or_else(|| format!("block {} not found", number))?
+                    .into();
+                self.rpc_client()
+                    .submit_block(number.to_string(), block.data().into())
+                    .map_err(|err| err.to_string())?;
+            }
+            start_number = end_number + 1;
         }
         Ok(())
     }
@@ -0,0 +1,140 @@
+use crate::{Error, Node};
+use ckb_types::{core::HeaderView, prelude::*};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The number of the past blocks which the median time is computed over
+pub const MEDIAN_TIME_BLOCK_COUNT: u64 = 37;
+
+/// ckb rejects the blocks whose timestamp is later than its local time by this, in
+/// milliseconds. The testkit submits such blocks via `process_block_without_verify`.
+const ALLOWED_FUTURE_BLOCKTIME: u64 = 15 * 1000;
+
+/// The mining clock of a node: every mined block gets the parent timestamp plus `step`, and
+/// the next one additionally gets `pending_jump`. All timestamps are in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VirtualClock {
+    pub step: u64,
+    pub pending_jump: u64,
+}
+
+impl Node {
+    /// Enable the virtual clock: every block mined afterwards via `mine` gets the parent
+    /// timestamp plus `step`, instead of the local time. It is shared with the clones of the
+    /// node.
+    ///
+    /// The blocks whose timestamps are later than the local time by more than 15 seconds are
+    /// submitted via `process_block_without_verify`, so other nodes may reject them.
+    pub fn enable_virtual_clock(&self, step: Duration) {
+        *self.virtual_clock.lock().expect("lock virtual_clock") = Some(VirtualClock {
+            step: step.as_millis() as u64,
+            pending_jump: 0,
+        });
+    }
+
+    pub fn disable_virtual_clock(&self) {
+        *self.virtual_clock.lock().expect("lock virtual_clock") = None;
+    }
+
+    pub fn virtual_clock(&self) -> Option<VirtualClock> {
+        *self.virtual_clock.lock().expect("lock virtual_clock")
+    }
+
+    /// Jump the virtual clock, so the next mined block gets the parent timestamp plus `step`
+    /// plus `duration`. Panics if the virtual clock is disabled.
+    pub fn advance_clock(&self, duration: Duration) {
+        self.try_advance_clock(duration)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_advance_clock(&self, duration: Duration) -> Result<(), Error> {
+        let mut virtual_clock = self.virtual_clock.lock().expect("lock virtual_clock");
+        let clock = virtual_clock.as_mut().ok_or_else(|| {
+            Error::Other(format!(
+                "virtual clock of node \"{}\" is disabled",
+                self.node_name()
+            ))
+        })?;
+        clock.pending_jump += duration.as_millis() as u64;
+        Ok(())
+    }
+
+    /// Return the timestamp of the next block on top of `parent` and consume the pending
+    /// jump, or `None` if the virtual clock is disabled.
+    pub(super) fn next_virtual_timestamp(&self, parent: &HeaderView) -> Option<u64> {
+        let mut virtual_clock = self.virtual_clock.lock().expect("lock virtual_clock");
+        let clock = virtual_clock.as_mut()?;
+        let timestamp = parent.timestamp() + clock.step + clock.pending_jump;
+        clock.pending_jump = 0;
+        Some(timestamp)
+    }
+
+    /// Return the median time of the tip, in milliseconds. The next block checks the
+    /// timestamp `since` of its transactions against it.
+    pub fn get_tip_median_time(&self) -> u64 {
+        self.try_get_tip_median_time()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_get_tip_median_time(&self) -> Result<u64, Error> {
+        self.ensure_alive()?;
+        let tip_hash = self.rpc_client().try_get_tip_header()?.hash;
+        self.rpc_client()
+            .try_get_block_median_time(tip_hash.pack())?
+            .map(|timestamp| timestamp.value())
+            .ok_or_else(|| Error::NotFound(format!("median time of block {:#x}", tip_hash)))
+    }
+
+    /// Mine blocks with the virtual clock until the median time of the tip reaches
+    /// `median_time`, in milliseconds. E.g. a transaction with input since
+    /// `since_from_absolute_timestamp(t)` is committable after
+    /// `mine_until_median_time(t * 1000)`.
+    pub fn mine_until_median_time(&self, median_time: u64) {
+        self.try_mine_until_median_time(median_time)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_mine_until_median_time(&self, median_time: u64) -> Result<(), Error> {
+        self.ensure_alive()?;
+        if self.virtual_clock().is_none() {
+            return Err(Error::Other(format!(
+                "virtual clock of node \"{}\" is disabled",
+                self.node_name()
+            )));
+        }
+        if self.try_get_tip_median_time()? >= median_time {
+            return Ok(());
+        }
+        let tip_timestamp = self
+            .rpc_client()
+            .try_get_tip_header()?
+            .inner
+            .timestamp
+            .value();
+        if tip_timestamp < median_time {
+            self.try_advance_clock(Duration::from_millis(median_time - tip_timestamp))?;
+        }
+        // After the jump, the median time reaches `median_time` once more than half of the
+        // past `MEDIAN_TIME_BLOCK_COUNT` blocks are mined after the jump
+        for _ in 0..MEDIAN_TIME_BLOCK_COUNT {
+            self.try_mine(1)?;
+            if self.try_get_tip_median_time()? >= median_time {
+                return Ok(());
+            }
+        }
+        Err(Error::Other(format!(
+            "median time of node \"{}\" does not reach {} after mining {} blocks",
+            self.node_name(),
+            median_time,
+            MEDIAN_TIME_BLOCK_COUNT
+        )))
+    }
+}
+
+/// Whether ckb accepts the block of `timestamp` via `submit_block`
+pub(super) fn is_acceptable_timestamp(timestamp: u64) -> bool {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time after unix epoch")
+        .as_millis() as u64;
+    timestamp <= now + ALLOWED_FUTURE_BLOCKTIME
+}
@@ -0,0 +1,461 @@
+use crate::SYSTEM_CELL_ALWAYS_SUCCESS_INDEX;
+use ckb_hash::blake2b_256;
+use ckb_types::{
+    core::{Capacity, EpochNumberWithFraction, ScriptHashType},
+    h256,
+    packed::{CellInput, Script},
+    prelude::*,
+    H256,
+};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The code hash of the type-id script, "TYPE_ID" in ascii
+pub const TYPE_ID_CODE_HASH: H256 =
+    h256!("0x00000000000000000000000000000000000000000000000000545950455f4944");
+
+/// The path of the rendered chain spec, relative to the working dir
+pub const CHAIN_SPEC_FILE: &str = "specs/dev.toml";
+
+/// The lock script of the always-success system cell referenced by type.
+///
+/// The genesis cellbase input is fixed, so the type-id args of the always-success cell, which
+/// is the `SYSTEM_CELL_ALWAYS_SUCCESS_INDEX`th output, is known before the chain starts. It
+/// equals to `Node::always_success_script` of the nodes initialized from `ChainSpecBuilder`.
+pub fn always_success_lock_script() -> Script {
+    let cellbase_input = CellInput::new_cellbase_input(0);
+    let mut type_id_args = cellbase_input.as_slice().to_vec();
+    type_id_args.extend_from_slice(&(SYSTEM_CELL_ALWAYS_SUCCESS_INDEX as u64).to_le_bytes());
+    let type_id_script = Script::new_builder()
+        .code_hash(TYPE_ID_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(blake2b_256(type_id_args).to_vec().pack())
+        .build();
+    Script::new_builder()
+        .code_hash(type_id_script.calc_script_hash())
+        .hash_type(ScriptHashType::Type.into())
+        .build()
+}
+
+fn script_json(script: &Script) -> serde_json::Value {
+    serde_json::to_value(ckb_jsonrpc_types::Script::from(script.clone())).expect("serialize script")
+}
+
+/// Render a script as TOML inline table
+fn script_toml(script: &Script) -> String {
+    let json = script_json(script);
+    format!(
+        "{{ code_hash = {}, hash_type = {}, args = {} }}",
+        json["code_hash"], json["hash_type"], json["args"]
+    )
+}
+
+/// Programmatic chain spec, rendered into `specs/dev.toml` of the working dir.
+///
+/// The genesis system cells are laid out as the template chain specs of the testkit: the
+/// bundled sighash_all, dao, secp256k1_data and multisig_all cells, then the always-success
+/// cell at `SYSTEM_CELL_ALWAYS_SUCCESS_INDEX`.
+#[derive(Debug, Clone)]
+pub struct ChainSpecBuilder {
+    name: String,
+    always_success_cell: PathBuf,
+    issued_cells: Vec<(Capacity, Script)>,
+    genesis_epoch_length: u64,
+    epoch_duration_target: u64,
+    cellbase_maturity: u64,
+    max_block_cycles: u64,
+    permanent_difficulty_in_dummy: bool,
+    hardfork: BTreeMap<String, u64>,
+}
+
+impl Default for ChainSpecBuilder {
+    fn default() -> Self {
+        Self {
+            name: "ckb_dev".to_string(),
+            always_success_cell: PathBuf::new(),
+            issued_cells: Vec::new(),
+            genesis_epoch_length: 1000,
+            epoch_duration_target: 14400,
+            cellbase_maturity: 0,
+            max_block_cycles: 10_000_000_000,
+            permanent_difficulty_in_dummy: true,
+            hardfork: BTreeMap::new(),
+        }
+    }
+}
+
+impl ChainSpecBuilder {
+    /// `always_success_cell` is the path of the always-success script binary
+    pub fn new<P: Into<PathBuf>>(always_success_cell: P) -> Self {
+        Self {
+            always_success_cell: always_success_cell.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn name<S: ToString>(mut self, name: S) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn issued_cell(mut self, capacity: Capacity, lock: Script) -> Self {
+        self.issued_cells.push((capacity, lock));
+        self
+    }
+
+    pub fn genesis_epoch_length(mut self, genesis_epoch_length: u64) -> Self {
+        self.genesis_epoch_length = genesis_epoch_length;
+        self
+    }
+
+    pub fn epoch_duration_target(mut self, epoch_duration_target: u64) -> Self {
+        self.epoch_duration_target = epoch_duration_target;
+        self
+    }
+
+    /// Cellbase maturity, in epochs
+    pub fn cellbase_maturity(mut self, cellbase_maturity: u64) -> Self {
+        self.cellbase_maturity = cellbase_maturity;
+        self
+    }
+
+    pub fn max_block_cycles(mut self, max_block_cycles: u64) -> Self {
+        self.max_block_cycles = max_block_cycles;
+        self
+    }
+
+    pub fn permanent_difficulty_in_dummy(mut self, permanent_difficulty_in_dummy: bool) -> Self {
+        self.permanent_difficulty_in_dummy = permanent_difficulty_in_dummy;
+        self
+    }
+
+    /// Activate the hardfork switch `rfc` at `epoch`, e.g. `.hardfork("rfc_0028", 0)`. It is
+    /// rendered as `rfc = epoch` inside `[params.hardfork]`.
+    pub fn hardfork<S: ToString>(mut self, rfc: S, epoch: u64) -> Self {
+        self.hardfork.insert(rfc.to_string(), epoch);
+        self
+    }
+
+    /// Write the chain spec and the always-success cell into `working_dir`
+    pub fn render(&self, working_dir: &Path) -> io::Result<()> {
+        let spec_path = working_dir.join(CHAIN_SPEC_FILE);
+        let spec_dir = spec_path.parent().expect("spec file has parent dir");
+        fs::create_dir_all(spec_dir.join("cells"))?;
+        fs::copy(
+            &self.always_success_cell,
+            spec_dir.join("cells/always_success"),
+        )
+        .map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!(
+                    "failed to copy always_success cell {}, error: {}",
+                    self.always_success_cell.display(),
+                    err
+                ),
+            )
+        })?;
+        fs::write(spec_path, self.to_toml())
+    }
+
+    pub fn to_toml(&self) -> String {
+        let zero_lock = r#"{ code_hash = "0x0000000000000000000000000000000000000000000000000000000000000000", hash_type = "data", args = "0x" }"#;
+        let mut issued_cells = String::new();
+        for (capacity, lock) in self.issued_cells.iter() {
+            issued_cells.push_str(&format!(
+                "[[genesis.issued_cells]]\ncapacity = {}\nlock = {}\n\n",
+                capacity.as_u64(),
+                script_toml(lock)
+            ));
+        }
+        let hardfork = if self.hardfork.is_empty() {
+            String::new()
+        } else {
+            let switches = self
+                .hardfork
+                .iter()
+                .map(|(rfc, epoch)| format!("{} = {}\n", rfc, epoch))
+                .collect::<String>();
+            format!("[params.hardfork]\n{}\n", switches)
+        };
+        let cellbase_maturity =
+            EpochNumberWithFraction::new(self.cellbase_maturity, 0, 1).full_value();
+        format!(
+            r#"name = "{name}"
+
+[genesis]
+version = 0
+parent_hash = "0x0000000000000000000000000000000000000000000000000000000000000000"
+timestamp = 0
+compact_target = 0x20010000
+uncles_hash = "0x0000000000000000000000000000000000000000000000000000000000000000"
+nonce = "0x0"
+
+[genesis.genesis_cell]
+message = "ckb-testkit"
+lock = {zero_lock}
+
+[[genesis.system_cells]]
+file = {{ bundled = "specs/cells/secp256k1_blake160_sighash_all" }}
+create_type_id = true
+capacity = 100_000_0000_0000
+[[genesis.system_cells]]
+file = {{ bundled = "specs/cells/dao" }}
+create_type_id = true
+capacity = 16_000_0000_0000
+[[genesis.system_cells]]
+file = {{ bundled = "specs/cells/secp256k1_data" }}
+create_type_id = false
+capacity = 1_048_617_0000_0000
+[[genesis.system_cells]]
+file = {{ bundled = "specs/cells/secp256k1_blake160_multisig_all" }}
+create_type_id = true
+capacity = 100_000_0000_0000
+[[genesis.system_cells]]
+file = {{ file = "cells/always_success" }}
+create_type_id = true
+capacity = 5_000_0000_0000
+
+[genesis.system_cells_lock]
+code_hash = "0x0000000000000000000000000000000000000000000000000000000000000000"
+args = "0x"
+hash_type = "data"
+
+[[genesis.dep_groups]]
+name = "secp256k1_blake160_sighash_all"
+files = [
+  {{ bundled = "specs/cells/secp256k1_data" }},
+  {{ bundled = "specs/cells/secp256k1_blake160_sighash_all" }},
+]
+[[genesis.dep_groups]]
+name = "secp256k1_blake160_multisig_all"
+files = [
+  {{ bundled = "specs/cells/secp256k1_data" }},
+  {{ bundled = "specs/cells/secp256k1_blake160_multisig_all" }},
+]
+
+[genesis.bootstrap_lock]
+code_hash = "0x0000000000000000000000000000000000000000000000000000000000000000"
+args = "0x"
+hash_type = "type"
+
+{issued_cells}[params]
+initial_primary_epoch_reward = 1_917_808_21917808
+secondary_epoch_reward = 613_698_63013698
+max_block_cycles = {max_block_cycles}
+cellbase_maturity = {cellbase_maturity}
+primary_epoch_reward_halving_interval = 8760
+epoch_duration_target = {epoch_duration_target}
+genesis_epoch_length = {genesis_epoch_length}
+permanent_difficulty_in_dummy = {permanent_difficulty_in_dummy}
+
+{hardfork}[pow]
+func = "Dummy"
+"#,
+            name = self.name,
+            zero_lock = zero_lock,
+            issued_cells = issued_cells,
+            max_block_cycles = self.max_block_cycles,
+            cellbase_maturity = cellbase_maturity,
+            epoch_duration_target = self.epoch_duration_target,
+            genesis_epoch_length = self.genesis_epoch_length,
+            permanent_difficulty_in_dummy = self.permanent_difficulty_in_dummy,
+            hardfork = hardfork,
+        )
+    }
+}
+
+/// Programmatic ckb.toml, rendered into the working dir with the allocated ports.
+#[derive(Debug, Clone)]
+pub struct AppConfigBuilder {
+    spec_file: String,
+    logger_filter: String,
+    rpc_modules: Vec<String>,
+    block_assembler: Option<Script>,
+    max_tx_pool_size: u64,
+    min_fee_rate: u64,
+    max_tx_verify_cycles: u64,
+    max_ancestors_count: u64,
+    discovery_local_address: bool,
+}
+
+impl Default for AppConfigBuilder {
+    fn default() -> Self {
+        Self {
+            spec_file: CHAIN_SPEC_FILE.to_string(),
+            logger_filter: "info".to_string(),
+            rpc_modules: [
+                "Net",
+                "Pool",
+                "Miner",
+                "Chain",
+                "Stats",
+                "Subscription",
+                "Experiment",
+                "Debug",
+                "IntegrationTest",
+                "Indexer",
+            ]
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+            block_assembler: Some(always_success_lock_script()),
+            max_tx_pool_size: 180_000_000,
+            min_fee_rate: 1_000,
+            max_tx_verify_cycles: 70_000_000,
+            max_ancestors_count: 25,
+            discovery_local_address: true,
+        }
+    }
+}
+
+impl AppConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The chain spec file, relative to the working dir. Default is `CHAIN_SPEC_FILE`, where
+    /// `ChainSpecBuilder` renders to.
+    pub fn spec_file<S: ToString>(mut self, spec_file: S) -> Self {
+        self.spec_file = spec_file.to_string();
+        self
+    }
+
+    /// The logger filter, e.g. "info,ckb-sync=debug"
+    pub fn logger_filter<S: ToString>(mut self, logger_filter: S) -> Self {
+        self.logger_filter = logger_filter.to_string();
+        self
+    }
+
+    pub fn rpc_modules<S: ToString>(mut self, rpc_modules: &[S]) -> Self {
+        self.rpc_modules = rpc_modules.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// The lock of the cellbase outputs. Default is `always_success_lock_script()`; `None`
+    /// disables the block assembler.
+    pub fn block_assembler(mut self, block_assembler: Option<Script>) -> Self {
+        self.block_assembler = block_assembler;
+        self
+    }
+
+    pub fn max_tx_pool_size(mut self, max_tx_pool_size: u64) -> Self {
+        self.max_tx_pool_size = max_tx_pool_size;
+        self
+    }
+
+    pub fn min_fee_rate(mut self, min_fee_rate: u64) -> Self {
+        self.min_fee_rate = min_fee_rate;
+        self
+    }
+
+    pub fn max_tx_verify_cycles(mut self, max_tx_verify_cycles: u64) -> Self {
+        self.max_tx_verify_cycles = max_tx_verify_cycles;
+        self
+    }
+
+    pub fn max_ancestors_count(mut self, max_ancestors_count: u64) -> Self {
+        self.max_ancestors_count = max_ancestors_count;
+        self
+    }
+
+    /// Whether the loopback addresses learned via discovery are dialed. Disable it to keep the
+    /// local nodes connected only along the edges built by `Nodes`, e.g. during a partition.
+    pub fn discovery_local_address(mut self, discovery_local_address: bool) -> Self {
+        self.discovery_local_address = discovery_local_address;
+        self
+    }
+
+    /// Write ckb.toml into `working_dir`
+    pub fn render(&self, working_dir: &Path, rpc_port: u16, p2p_port: u16) -> io::Result<()> {
+        fs::write(
+            working_dir.join("ckb.toml"),
+            self.to_toml(rpc_port, p2p_port),
+        )
+    }
+
+    pub fn to_toml(&self, rpc_port: u16, p2p_port: u16) -> String {
+        let rpc_modules = self
+            .rpc_modules
+            .iter()
+            .map(|module| format!("\"{}\"", module))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let block_assembler = match self.block_assembler {
+            Some(ref lock) => {
+                let json = script_json(lock);
+                format!(
+                    "[block_assembler]\ncode_hash = {}\nhash_type = {}\nargs = {}\nmessage = \"0x\"\n",
+                    json["code_hash"], json["hash_type"], json["args"]
+                )
+            }
+            None => String::new(),
+        };
+        format!(
+            r#"data_dir = "data"
+
+[chain]
+spec = {{ file = "{spec_file}" }}
+
+[logger]
+filter = "{logger_filter}"
+color = false
+log_to_file = true
+log_to_stdout = true
+
+[sentry]
+dsn = ""
+
+[db]
+cache_size = 268435456
+
+[network]
+listen_addresses = ["/ip4/0.0.0.0/tcp/{p2p_port}"]
+bootnodes = []
+max_peers = 125
+max_outbound_peers = 8
+ping_interval_secs = 120
+ping_timeout_secs = 1200
+connect_outbound_interval_secs = 15
+upnp = false
+discovery_local_address = {discovery_local_address}
+bootnode_mode = false
+
+[rpc]
+listen_address = "127.0.0.1:{rpc_port}"
+max_request_body_size = 10485760
+modules = [{rpc_modules}]
+reject_ill_transactions = true
+enable_deprecated_rpc = false
+
+[tx_pool]
+max_tx_pool_size = {max_tx_pool_size}
+min_fee_rate = {min_fee_rate}
+max_tx_verify_cycles = {max_tx_verify_cycles}
+max_ancestors_count = {max_ancestors_count}
+
+[store]
+header_cache_size = 4096
+cell_data_cache_size = 128
+block_proposals_cache_size = 30
+block_tx_hashes_cache_size = 30
+block_uncles_cache_size = 30
+cellbase_cache_size = 30
+
+{block_assembler}"#,
+            spec_file = self.spec_file,
+            logger_filter = self.logger_filter,
+            p2p_port = p2p_port,
+            rpc_port = rpc_port,
+            rpc_modules = rpc_modules,
+            max_tx_pool_size = self.max_tx_pool_size,
+            min_fee_rate = self.min_fee_rate,
+            max_tx_verify_cycles = self.max_tx_verify_cycles,
+            max_ancestors_count = self.max_ancestors_count,
+            discovery_local_address = self.discovery_local_address,
+            block_assembler = block_assembler,
+        )
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{Error, Node, DAO_LOCK_PERIOD_EPOCHS, SYSTEM_CELL_DAO_INDEX};
+use ckb_types::{
+    core::{
+        cell::{CellMeta, CellMetaBuilder},
+        EpochNumberWithFraction, HeaderView, ScriptHashType, TransactionInfo,
+    },
+    packed::{Byte32, CellDep, OutPoint, Script},
+    prelude::*,
+};
+use std::convert::TryInto;
+
+/// The DAO field of block header, see
+/// https://github.com/nervosnetwork/rfcs/blob/master/rfcs/0023-dao-deposit-withdraw/0023-dao-deposit-withdraw.md
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DaoField {
+    /// The total issuance
+    pub c: u64,
+    /// The accumulated rate, 10^16 based
+    pub ar: u64,
+    /// The total unissued secondary issuance
+    pub s: u64,
+    /// The total occupied capacity
+    pub u: u64,
+}
+
+impl DaoField {
+    pub fn from_dao(dao: &Byte32) -> Self {
+        let raw = dao.raw_data();
+        let field = |i: usize| u64::from_le_bytes(raw[i * 8..(i + 1) * 8].try_into().unwrap());
+        Self {
+            c: field(0),
+            ar: field(1),
+            s: field(2),
+            u: field(3),
+        }
+    }
+
+    pub fn from_header(header: &HeaderView) -> Self {
+        Self::from_dao(&header.dao())
+    }
+}
+
+/// Return the earliest epoch at which the prepared DAO cell can be withdrawn, the same as
+/// `minimal_unlock_point` of ckb-sdk.
+pub fn minimal_unlock_point(
+    deposit_header: &HeaderView,
+    prepare_header: &HeaderView,
+) -> EpochNumberWithFraction {
+    let deposit_point = deposit_header.epoch();
+    let prepare_point = prepare_header.epoch();
+    let prepare_fraction = prepare_point.index() * deposit_point.length();
+    let deposit_fraction = deposit_point.index() * prepare_point.length();
+    let passed_epoch_cnt = if prepare_fraction > deposit_fraction {
+        prepare_point.number() - deposit_point.number() + 1
+    } else {
+        prepare_point.number() - deposit_point.number()
+    };
+    let rest_epoch_cnt = (passed_epoch_cnt + (DAO_LOCK_PERIOD_EPOCHS - 1)) / DAO_LOCK_PERIOD_EPOCHS
+        * DAO_LOCK_PERIOD_EPOCHS;
+    EpochNumberWithFraction::new(
+        deposit_point.number() + rest_epoch_cnt,
+        deposit_point.index(),
+        deposit_point.length(),
+    )
+}
+
+impl Node {
+    pub fn dao_type_script(&self) -> Script {
+        let type_ = self
+            .genesis_block()
+            .transaction(0)
+            .expect("genesis cellbase")
+            .output(SYSTEM_CELL_DAO_INDEX as usize)
+            .expect("genesis dao cell")
+            .type_()
+            .to_opt()
+            .expect("genesis dao cell should have type_=type-id script");
+        Script::new_builder()
+            .code_hash(type_.calc_script_hash())
+            .hash_type(ScriptHashType::Type.into())
+            .build()
+    }
+
+    pub fn dao_cell_dep(&self) -> CellDep {
+        CellDep::new_builder()
+            .out_point(OutPoint::new(
+                self.genesis_cellbase_hash(),
+                SYSTEM_CELL_DAO_INDEX,
+            ))
+            .build()
+    }
+
+    /// Return the parsed DAO field of the header of `hash`
+    pub fn get_dao_field(&self, hash: Byte32) -> DaoField {
+        self.try_get_dao_field(hash)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_get_dao_field(&self, hash: Byte32) -> Result<DaoField, Error> {
+        self.ensure_alive()?;
+        let header: HeaderView = self
+            .rpc_client()
+            .try_get_header(hash.clone())?
+            .ok_or_else(|| Error::NotFound(format!("header {:#x}", hash)))?
+            .into();
+        Ok(DaoField::from_header(&header))
+    }
+
+    /// Return the committed live cell of `out_point`, with its data and transaction info.
+    pub fn get_cell_meta(&self, out_point: OutPoint) -> Option<CellMeta> {
+        self.try_get_cell_meta(out_point)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_get_cell_meta(&self, out_point: OutPoint) -> Result<Option<CellMeta>, Error> {
+        self.ensure_alive()?;
+        let rpc_client = self.rpc_client();
+        let cell = match rpc_client
+            .try_get_live_cell(out_point.clone().into(), true)?
+            .cell
+        {
+            Some(cell) => cell,
+            None => return Ok(None),
+        };
+        let tx_hash = out_point.tx_hash();
+        let block_hash = match rpc_client
+            .try_get_transaction(tx_hash.clone())?
+            .and_then(|tx| tx.tx_status.block_hash)
+        {
+            Some(block_hash) => block_hash,
+            None => return Ok(None),
+        };
+        let block = rpc_client
+            .try_get_block(block_hash.pack())?
+            .ok_or_else(|| Error::NotFound(format!("block {:#x}", block_hash)))?;
+        let tx_index = block
+            .transactions
+            .iter()
+            .position(|tx| tx.hash == tx_hash.unpack())
+            .ok_or_else(|| Error::NotFound(format!("transaction {:#x}", tx_hash)))?;
+        let header: HeaderView = block.header.into();
+        let data = cell
+            .data
+            .map(|data| data.content.into_bytes())
+            .unwrap_or_default();
+        let cell_meta = CellMetaBuilder::from_cell_output(cell.output.into(), data)
+            .out_point(out_point)
+            .transaction_info(TransactionInfo::new(
+                header.number(),
+                header.epoch(),
+                header.hash(),
+                tx_index,
+            ))
+            .build();
+        Ok(Some(cell_meta))
+    }
+}
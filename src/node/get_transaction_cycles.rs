@@ -2,7 +2,8 @@ use crate::Node;
 use ckb_types::core::{Cycle, TransactionView};
 
 impl Node {
-    /// Get the transaction cycles via RPC `dry_run_transaction`.
+    /// Get the transaction cycles via RPC `estimate_cycles`, or `dry_run_transaction` before
+    /// ckb v0.105.
     ///
     /// NOTE: Transaction runs on different VM comsumes different cycles.
     /// Therefore, if a transaction triggers a script with `ScriptHashType::Type`,
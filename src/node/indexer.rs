@@ -0,0 +1,152 @@
+use crate::util::wait_until;
+use crate::{Error, Node};
+use ckb_jsonrpc_types::{IndexerCell, IndexerScriptType, IndexerSearchKey, JsonBytes};
+use ckb_types::core::cell::{CellMeta, CellMetaBuilder};
+use ckb_types::core::{EpochNumberWithFraction, HeaderView, TransactionInfo};
+use ckb_types::packed::{OutPoint, Script};
+
+impl Node {
+    /// Wait until the built-in indexer catches up with the chain tip.
+    ///
+    /// The node must run with the indexer enabled, i.e. `NodeOptions::indexer(true)` and the
+    /// `Indexer` module listed in `[rpc].modules` of ckb.toml.
+    pub fn wait_for_indexer(&self) {
+        self.try_wait_for_indexer()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_wait_for_indexer(&self) -> Result<(), Error> {
+        self.ensure_alive()?;
+        let rpc_client = self.rpc_client();
+        let mut rpc_error = None;
+        let mut tip_number = 0;
+        let mut indexer_tip_number = None;
+        let synced = wait_until(20, || {
+            let result = rpc_client.try_get_tip_block_number().and_then(|number| {
+                tip_number = number;
+                rpc_client.try_get_indexer_tip()
+            });
+            match result {
+                Ok(indexer_tip) => {
+                    indexer_tip_number = indexer_tip.map(|tip| tip.block_number.value());
+                    indexer_tip_number.map(|number| number >= tip_number) == Some(true)
+                }
+                Err(err) => {
+                    rpc_error = Some(err);
+                    true
+                }
+            }
+        });
+        if let Some(err) = rpc_error {
+            return Err(err);
+        }
+        if !synced {
+            return Err(Error::Timeout(format!(
+                "[Node {}] wait for indexer, chain tip: {}, indexer tip: {:?}",
+                self.node_name(),
+                tip_number,
+                indexer_tip_number,
+            )));
+        }
+        Ok(())
+    }
+
+    /// Return all the live cells locked by `lock_script`, with their data.
+    /// Return all the live cells locked by `lock_script` via the built-in indexer.
+    ///
+    /// The node must run with `NodeOptions::indexer(true)`, otherwise an error is returned
+    /// immediately. The remote nodes are assumed to run with the indexer.
+    pub fn get_live_cells_by_lock_script(&self, lock_script: &Script) -> Vec<IndexerCell> {
+        self.try_get_live_cells_by_lock_script(lock_script)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_get_live_cells_by_lock_script(
+        &self,
+        lock_script: &Script,
+    ) -> Result<Vec<IndexerCell>, Error> {
+        self.ensure_alive()?;
+        if !self.is_remote() && !self.node_options().indexer {
+            return Err(Error::Other(format!(
+                "node \"{}\" runs without the indexer, start it with `NodeOptions::indexer(true)` \
+                 to look up cells",
+                self.node_name()
+            )));
+        }
+        self.try_wait_for_indexer()?;
+        let search_key = IndexerSearchKey {
+            script: lock_script.clone().into(),
+            script_type: IndexerScriptType::Lock,
+            filter: None,
+            with_data: Some(true),
+            group_by_transaction: None,
+        };
+        self.rpc_client().try_get_all_cells(search_key)
+    }
+
+    /// Return the live cells locked by `lock_script` that can be spent as plain capacity, i.e.
+    /// without type script, without data and, for cellbase cells, mature. The node must run
+    /// with `NodeOptions::indexer(true)`.
+    pub fn get_spendable_cells_by_lock_script(&self, lock_script: &Script) -> Vec<CellMeta> {
+        self.try_get_spendable_cells_by_lock_script(lock_script)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_get_spendable_cells_by_lock_script(
+        &self,
+        lock_script: &Script,
+    ) -> Result<Vec<CellMeta>, Error> {
+        self.ensure_alive()?;
+        let live_cells = self
+            .try_get_live_cells_by_lock_script(lock_script)?
+            .into_iter()
+            .filter(|cell| {
+                cell.output.type_.is_none()
+                    && cell
+                        .output_data
+                        .as_ref()
+                        .map(|data| data.is_empty())
+                        .unwrap_or(true)
+            })
+            .collect::<Vec<_>>();
+
+        let rpc_client = self.rpc_client();
+        let headers = rpc_client
+            .try_get_headers_by_number(live_cells.iter().map(|cell| cell.block_number.value()))?;
+        let tip_epoch = EpochNumberWithFraction::from_full_value(
+            rpc_client.try_get_tip_header()?.inner.epoch.value(),
+        );
+        let cellbase_maturity =
+            EpochNumberWithFraction::from_full_value(self.consensus().cellbase_maturity.value());
+        let mut cells = Vec::with_capacity(live_cells.len());
+        for (cell, header) in live_cells.into_iter().zip(headers) {
+            let block_number = cell.block_number.value();
+            let header: HeaderView = header
+                .ok_or_else(|| Error::NotFound(format!("header {}", block_number)))?
+                .into();
+            let tx_index = cell.tx_index.value() as usize;
+            let is_cellbase = tx_index == 0 && block_number > 0;
+            if is_cellbase {
+                let threshold = header.epoch().to_rational() + cellbase_maturity.to_rational();
+                if tip_epoch.to_rational() < threshold {
+                    continue;
+                }
+            }
+
+            let out_point: OutPoint = cell.out_point.into();
+            let transaction_info =
+                TransactionInfo::new(block_number, header.epoch(), header.hash(), tx_index);
+            let output_data = cell
+                .output_data
+                .map(JsonBytes::into_bytes)
+                .unwrap_or_default();
+            cells.push(
+                CellMetaBuilder::from_cell_output(cell.output.into(), output_data)
+                    .out_point(out_point)
+                    .transaction_info(transaction_info)
+                    .build(),
+            );
+        }
+        Ok(cells)
+    }
+}
@@ -1,21 +1,242 @@
-use crate::Node;
-use ckb_types::core::BlockNumber;
-use ckb_types::packed;
+use super::clock::is_acceptable_timestamp;
+use crate::{Error, Node};
+use ckb_types::core::{BlockNumber, EpochNumber, EpochNumberWithFraction, HeaderView};
+use ckb_types::{packed, prelude::*};
+
+/// Where `Node::mine` gets the blocks from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MiningBackend {
+    /// Use `Integration`, and switch to `Template` for good once the node does not serve the
+    /// IntegrationTest RPCs
+    Auto,
+    /// The IntegrationTest RPCs `generate_block` and `generate_epochs`, which assemble and
+    /// process the blocks inside the node. The tx-pool is waited once per call.
+    Integration,
+    /// `get_block_template` and `submit_block`, waiting for the tx-pool after every block
+    Template,
+}
+
+impl Default for MiningBackend {
+    fn default() -> Self {
+        MiningBackend::Auto
+    }
+}
 
 impl Node {
+    /// Set the mining backend, which is shared with the clones of the node
+    pub fn set_mining_backend(&self, backend: MiningBackend) {
+        *self.mining_backend.lock().expect("lock mining_backend") = backend;
+    }
+
+    pub fn mining_backend(&self) -> MiningBackend {
+        *self.mining_backend.lock().expect("lock mining_backend")
+    }
+
+    /// Mine `n_blocks` blocks. The blocks are mined via the template path when the virtual
+    /// clock is enabled, as their timestamps are set by the testkit.
     pub fn mine(&self, n_blocks: u64) {
+        self.try_mine(n_blocks)
+            .unwrap_or_else(|err| panic!("failed to mine, error: {}", err))
+    }
+
+    pub fn try_mine(&self, n_blocks: u64) -> Result<(), Error> {
+        self.ensure_alive()?;
+        if n_blocks == 0 {
+            return Ok(());
+        }
+        if self.virtual_clock().is_none() && self.mining_backend() != MiningBackend::Template {
+            match self.try_generate_blocks(n_blocks) {
+                Ok(()) => return Ok(()),
+                Err(err) if self.fall_back_to_template(&err) => {}
+                Err(err) => return Err(err),
+            }
+        }
         for _ in 0..n_blocks {
-            let template = self.rpc_client().get_block_template(None, None, None);
-            let block = packed::Block::from(template).into_view();
-            self.submit_block(&block);
+            let mut template = self.rpc_client().try_get_block_template(None, None, None)?;
+            if self.virtual_clock().is_some() {
+                let parent: HeaderView = self
+                    .rpc_client()
+                    .try_get_header(template.parent_hash.pack())?
+                    .ok_or_else(|| Error::NotFound(format!("header {:#x}", template.parent_hash)))?
+                    .into();
+                let timestamp = self
+                    .next_virtual_timestamp(&parent)
+                    .expect("checked virtual clock");
+                template.current_time = timestamp.into();
+                let block = packed::Block::from(template).into_view();
+                if is_acceptable_timestamp(timestamp) {
+                    self.try_submit_block(&block)?;
+                } else {
+                    self.rpc_client()
+                        .try_process_block_without_verify(block.data().into(), true)?;
+                    self.try_wait_for_tx_pool()?;
+                }
+            } else {
+                let block = packed::Block::from(template).into_view();
+                self.try_submit_block(&block)?;
+            }
         }
+        Ok(())
     }
 
     pub fn mine_to(&self, target_height: BlockNumber) {
-        let tip_number = self.get_tip_block_number();
+        self.try_mine_to(target_height)
+            .unwrap_or_else(|err| panic!("failed to mine, error: {}", err))
+    }
+
+    pub fn try_mine_to(&self, target_height: BlockNumber) -> Result<(), Error> {
+        self.ensure_alive()?;
+        let tip_number = self.try_get_tip_block_number()?;
         if tip_number < target_height {
             let n_blocks = target_height - tip_number;
-            self.mine(n_blocks);
+            self.try_mine(n_blocks)?;
+        }
+        Ok(())
+    }
+
+    /// Mine until the tip reaches `target`. The fraction of `target` is scaled to the actual
+    /// length of the target epoch, e.g. 1/2 means the middle of the epoch, whatever its
+    /// length is.
+    pub fn mine_to_epoch(&self, target: EpochNumberWithFraction) {
+        self.try_mine_to_epoch(target)
+            .unwrap_or_else(|err| panic!("failed to mine, error: {}", err))
+    }
+
+    pub fn try_mine_to_epoch(&self, target: EpochNumberWithFraction) -> Result<(), Error> {
+        self.ensure_alive()?;
+        let (numer, denom) = epoch_to_rational(target);
+        self.try_mine_to_rational_epoch(numer, denom)
+    }
+
+    /// Mine until the tip reaches the first block of the `n`-th epoch after the current one
+    pub fn mine_epochs(&self, n: EpochNumber) {
+        self.try_mine_epochs(n)
+            .unwrap_or_else(|err| panic!("failed to mine, error: {}", err))
+    }
+
+    pub fn try_mine_epochs(&self, n: EpochNumber) -> Result<(), Error> {
+        self.ensure_alive()?;
+        let tip_epoch = self.try_get_tip_block()?.epoch();
+        self.try_mine_to_epoch(EpochNumberWithFraction::new(tip_epoch.number() + n, 0, 1))
+    }
+
+    /// Mine until the tip is in the activation epoch of the hardfork `feature`, e.g. "0028"
+    /// or "rfc_0028", as listed in `Consensus::hardfork_features`.
+    pub fn mine_until_hardfork(&self, feature: &str) {
+        self.try_mine_until_hardfork(feature)
+            .unwrap_or_else(|err| panic!("failed to mine, error: {}", err))
+    }
+
+    pub fn try_mine_until_hardfork(&self, feature: &str) -> Result<(), Error> {
+        self.ensure_alive()?;
+        let rfc = feature.trim_start_matches("rfc_");
+        let hardfork_feature = self
+            .consensus()
+            .hardfork_features
+            .iter()
+            .find(|hardfork_feature| hardfork_feature.rfc.trim_start_matches("rfc_") == rfc)
+            .ok_or_else(|| Error::NotFound(format!("hardfork feature \"{}\"", feature)))?;
+        let epoch_number = hardfork_feature.epoch_number.ok_or_else(|| {
+            Error::Other(format!("hardfork feature \"{}\" is not scheduled", feature))
+        })?;
+        self.try_mine_to_epoch(EpochNumberWithFraction::new(epoch_number.value(), 0, 1))
+    }
+
+    /// Mine blocks for `num_epochs` epochs, i.e. until the tip epoch, as a rational number,
+    /// reaches the current one plus `num_epochs`. Return the epoch of the new tip.
+    pub fn generate_epochs(&self, num_epochs: EpochNumberWithFraction) -> EpochNumberWithFraction {
+        self.try_generate_epochs(num_epochs)
+            .unwrap_or_else(|err| panic!("failed to mine, error: {}", err))
+    }
+
+    pub fn try_generate_epochs(
+        &self,
+        num_epochs: EpochNumberWithFraction,
+    ) -> Result<EpochNumberWithFraction, Error> {
+        self.ensure_alive()?;
+        if self.virtual_clock().is_none() && self.mining_backend() != MiningBackend::Template {
+            match self.rpc_client().try_generate_epochs(num_epochs) {
+                Ok(epoch) => {
+                    self.try_wait_for_tx_pool()?;
+                    return Ok(epoch);
+                }
+                Err(err) if self.fall_back_to_template(&err) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        let (tip_numer, tip_denom) = epoch_to_rational(self.try_get_tip_block()?.epoch());
+        let (numer, denom) = epoch_to_rational(num_epochs);
+        self.try_mine_to_rational_epoch(tip_numer * denom + numer * tip_denom, tip_denom * denom)?;
+        Ok(self.try_get_tip_block()?.epoch())
+    }
+
+    // Mine until the tip epoch, as a rational number, reaches `numer / denom`
+    fn try_mine_to_rational_epoch(&self, numer: u128, denom: u128) -> Result<(), Error> {
+        let target_number = numer / denom;
+        loop {
+            let tip_epoch = self.try_get_tip_block()?.epoch();
+            let (number, index, length) = (
+                u128::from(tip_epoch.number()),
+                u128::from(tip_epoch.index()),
+                u128::from(tip_epoch.length()),
+            );
+            if number > target_number {
+                return Ok(());
+            }
+            if number < target_number {
+                // Mine to the first block of the next epoch, whose length is only known then
+                self.try_mine((length - index) as u64)?;
+                continue;
+            }
+            // Round up, so the tip is never before the target fraction
+            let target_index = ((numer % denom) * length + denom - 1) / denom;
+            if index >= target_index {
+                return Ok(());
+            }
+            self.try_mine((target_index - index) as u64)?;
         }
     }
+
+    // Generate the blocks via RPC `generate_block`, then wait for the tx-pool once
+    fn try_generate_blocks(&self, n_blocks: u64) -> Result<(), Error> {
+        for _ in 0..n_blocks {
+            self.rpc_client().try_generate_block()?;
+        }
+        self.try_wait_for_tx_pool()
+    }
+
+    // Switch the `Auto` backend to `Template` if `err` says that the node does not serve the
+    // IntegrationTest RPCs. Return whether it switched.
+    fn fall_back_to_template(&self, err: &Error) -> bool {
+        let unsupported = err
+            .as_rpc_error()
+            .map_or(false, |err| err.is_method_not_found());
+        if !unsupported {
+            return false;
+        }
+        let mut backend = self.mining_backend.lock().expect("lock mining_backend");
+        if *backend != MiningBackend::Auto {
+            return false;
+        }
+        crate::info!(
+            "[Node {}] IntegrationTest RPCs unavailable, mine via block templates: {}",
+            self.node_name(),
+            err
+        );
+        *backend = MiningBackend::Template;
+        true
+    }
+}
+
+/// Return the epoch as a rational number `numer / denom`
+fn epoch_to_rational(epoch: EpochNumberWithFraction) -> (u128, u128) {
+    if epoch.length() == 0 {
+        (u128::from(epoch.number()), 1)
+    } else {
+        let length = u128::from(epoch.length());
+        (
+            u128::from(epoch.number()) * length + u128::from(epoch.index()),
+            length,
+        )
+    }
 }
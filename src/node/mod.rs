@@ -1,16 +1,31 @@
 mod always_success;
 mod builder;
+mod clock;
+pub(crate) mod config;
+mod dao;
 mod genesis_block_info;
 mod get_transaction;
 mod get_transaction_cycles;
+mod indexer;
 mod mining;
 mod node;
 mod node_options;
 mod p2p;
 mod rpc;
+mod snapshot;
 #[cfg(feature = "with_subscribe")]
 mod subscribe;
+mod watchdog;
 
 pub use builder::BuildInstruction;
+pub use clock::{VirtualClock, MEDIAN_TIME_BLOCK_COUNT};
+pub use config::{
+    always_success_lock_script, AppConfigBuilder, ChainSpecBuilder, CHAIN_SPEC_FILE,
+    TYPE_ID_CODE_HASH,
+};
+pub use dao::{minimal_unlock_point, DaoField};
+pub use mining::MiningBackend;
 pub use node::Node;
-pub use node_options::NodeOptions;
+pub use node_options::{NodeOptions, OutputMode};
+pub use snapshot::{recorded_snapshot_hash, snapshot_content_hash, SNAPSHOT_HASH_FILE};
+pub use watchdog::POST_MORTEM_DIR_ENV;
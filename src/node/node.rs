@@ -1,34 +1,31 @@
+use super::clock::VirtualClock;
+use super::mining::MiningBackend;
+use super::watchdog::{PostMortem, Watchdog};
 use crate::error;
-use crate::rpc::RpcClient;
+use crate::rpc::{AsyncRpcClient, CkbVersion, RpcClient};
 use crate::util::{find_available_port, temp_path};
-use crate::NodeOptions;
-use ckb_jsonrpc_types::{Consensus, LocalNode};
+use crate::{Error, NodeOptions, OutputMode};
+use ckb_jsonrpc_types::{Consensus, LocalNode, NodeAddress};
 use ckb_types::core::BlockView;
 use fs_extra::dir::CopyOptions;
 use reqwest::Url;
 use std::fs;
-use std::path::PathBuf;
-use std::process::{self, Child, Command, Stdio};
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
 use std::thread::sleep;
 use std::time::{Duration, Instant};
 
-struct ProcessGuard(pub Child);
-
-impl Drop for ProcessGuard {
-    fn drop(&mut self) {
-        let _x = self
-            .0
-            .kill()
-            .map_err(|err| error!("failed to kill ckb process, error: {}", err));
-        let _y = self.0.wait();
-    }
-}
-
 pub struct Node {
     pub(super) node_options: NodeOptions,
 
-    pub(super) working_dir: PathBuf,
+    /// `None` for the remote nodes without a local working dir
+    pub(super) working_dir: Option<PathBuf>,
     pub(super) rpc_client: RpcClient,
+    /// Whether the ckb process is managed externally, see `Node::init_from_url`
+    pub(super) remote: bool,
 
     #[cfg(feature = "with_subscribe")]
     pub(super) new_tip_block_subscriber:
@@ -59,19 +56,26 @@ pub struct Node {
     pub(super) consensus: Option<Consensus>, // initialize when node start
     pub(super) genesis_block: Option<BlockView>, // initialize when node start
     pub(super) node_id: Option<String>,     // initialize when node start
-    _guard: Option<ProcessGuard>,           // initialize when node start
+    pub(super) crashed: Arc<Mutex<Option<ExitStatus>>>, // set by watchdog
+    pub(super) virtual_clock: Arc<Mutex<Option<VirtualClock>>>, // shared with clones
+    pub(super) mining_backend: Arc<Mutex<MiningBackend>>, // shared with clones
+    _guard: Option<Watchdog>,               // initialize when node start
 }
 
 impl Clone for Node {
     fn clone(&self) -> Node {
         Self {
             node_options: self.node_options.clone(),
-            working_dir: self.working_dir().clone(),
+            working_dir: self.working_dir.clone(),
             rpc_client: self.rpc_client.clone(),
+            remote: self.remote,
             p2p_address: self.p2p_address.clone(),
             consensus: self.consensus.clone(),
             genesis_block: self.genesis_block.clone(),
             node_id: self.node_id.clone(),
+            crashed: Arc::clone(&self.crashed),
+            virtual_clock: Arc::clone(&self.virtual_clock),
+            mining_backend: Arc::clone(&self.mining_backend),
             _guard: None,
             #[cfg(feature = "with_subscribe")]
             new_tip_block_subscriber: None,
@@ -88,19 +92,28 @@ impl Clone for Node {
 }
 
 impl Node {
-    pub fn init<S: ToString>(case_name: S, node_options: NodeOptions, is_ckb2021: bool) -> Self {
+    /// Initialize the working dir of the node. The ckb version is detected when the node
+    /// starts, so that nodes of different ckb binaries can be mixed in one network.
+    pub fn init<S: ToString>(case_name: S, node_options: NodeOptions) -> Self {
         let case_name = case_name.to_string();
         let rpc_port = find_available_port();
         let p2p_port = find_available_port();
         let working_dir = prepare_working_dir(&case_name, &node_options, rpc_port, p2p_port);
         Self {
             node_options,
-            working_dir,
-            rpc_client: RpcClient::new(&format!("http://127.0.0.1:{}/", rpc_port), is_ckb2021),
+            working_dir: Some(working_dir),
+            rpc_client: RpcClient::new(
+                &format!("http://127.0.0.1:{}/", rpc_port),
+                CkbVersion::default(),
+            ),
+            remote: false,
             p2p_address: None,
             consensus: None,
             genesis_block: None,
             node_id: None,
+            crashed: Default::default(),
+            virtual_clock: Default::default(),
+            mining_backend: Default::default(),
             _guard: None,
             #[cfg(feature = "with_subscribe")]
             new_tip_block_subscriber: None,
@@ -115,58 +128,29 @@ impl Node {
         }
     }
 
-    pub fn init_from_url(rpc_url: &str, working_dir: PathBuf) -> Self {
-        let mut rpc_client = RpcClient::new(rpc_url, true);
-        let local_node_info = rpc_client.local_node_info();
-        let is_ckb2021 = {
-            let node_version = &local_node_info.version;
-            let minimal_2021_version = "0.44.0";
-            let is_ckb2021 = version_compare::compare_to(
-                node_version,
-                minimal_2021_version,
-                version_compare::Cmp::Ge,
-            )
-            .unwrap_or(true);
-            is_ckb2021
-        };
-        if !is_ckb2021 {
-            rpc_client = RpcClient::new(&rpc_url, is_ckb2021)
-        }
-
-        let consensus = rpc_client.get_consensus();
-        let genesis_block = rpc_client
-            .get_block_by_number(0)
-            .expect("get genesis block");
-        let node_id = local_node_info.node_id.to_owned();
-        let p2p_address = {
-            let listened_address = local_node_info.addresses[0].address.clone();
-            let host_str = Url::parse(&rpc_url)
-                .expect("rpc_url is checked")
-                .host_str()
-                .expect("rpc_url has host")
-                .to_string();
-            let changed_listened_address = listened_address.replace("0.0.0.0", &host_str);
-            Some(changed_listened_address)
-        };
+    /// Initialize a handle of the externally managed ckb node at `rpc_url`, e.g. a
+    /// docker-compose devnet on localhost. `working_dir` is the node's working dir if it is
+    /// accessible locally, which is only used to locate the log file.
+    ///
+    /// The remote node is expected to be running. `start` reloads the node info via RPC, and
+    /// `stop` does nothing.
+    pub fn init_from_url(rpc_url: &str, working_dir: Option<PathBuf>) -> Self {
         let node_options = NodeOptions {
             node_name: rpc_url.to_string(),
             ..Default::default()
         };
-        crate::info!(
-            "init node, rpc_url: \"{}\", is_ckb2021: {}, p2p_address: {}",
-            rpc_url,
-            is_ckb2021,
-            p2p_address.as_ref().unwrap()
-        );
-        Self {
-            // TODO get p2p listen address via RPC
+        let mut node = Self {
             node_options,
             working_dir,
-            rpc_client,
-            p2p_address,
-            consensus: Some(consensus),
-            genesis_block: Some(genesis_block.into()),
-            node_id: Some(node_id),
+            rpc_client: RpcClient::new(rpc_url, CkbVersion::default()),
+            remote: true,
+            p2p_address: None,
+            consensus: None,
+            genesis_block: None,
+            node_id: None,
+            crashed: Default::default(),
+            virtual_clock: Default::default(),
+            mining_backend: Default::default(),
             _guard: None,
             #[cfg(feature = "with_subscribe")]
             new_tip_block_subscriber: None,
@@ -178,47 +162,141 @@ impl Node {
             proposed_transaction_subscriber: None,
             #[cfg(feature = "with_subscribe")]
             rejected_transaction_subscriber: None,
+        };
+        node.start();
+        node
+    }
+
+    /// Override the p2p address picked from `local_node_info`, e.g. when the p2p port of a
+    /// docker container is mapped to a different host port. E.g. "/ip4/127.0.0.1/tcp/18115"
+    pub fn with_p2p_address<S: ToString>(mut self, p2p_address: S) -> Self {
+        self.p2p_address = Some(p2p_address.to_string());
+        self
+    }
+
+    /// Whether the ckb process is managed externally, i.e. initialized via `init_from_url`
+    pub fn is_remote(&self) -> bool {
+        self.remote
+    }
+
+    fn try_load_remote_info(&mut self) -> Result<(), Error> {
+        let rpc_url = self.rpc_client.url().to_string();
+        let local_node_info = self.rpc_client.try_local_node_info()?;
+        self.detect_ckb_version(&local_node_info);
+
+        let consensus = self.rpc_client().try_get_consensus()?;
+        let genesis_block = self.try_get_block_by_number(0)?;
+        if self.p2p_address.is_none() {
+            let host = rpc_host(&rpc_url)?;
+            self.p2p_address = Some(
+                pick_p2p_address(&local_node_info.addresses, &host).ok_or_else(|| {
+                    Error::NotFound(format!("p2p listen address of node \"{}\"", rpc_url))
+                })?,
+            );
         }
+        self.consensus = Some(consensus);
+        self.genesis_block = Some(genesis_block);
+        self.node_id = Some(local_node_info.node_id);
+        crate::info!(
+            "init node, rpc_url: \"{}\", version: {}, p2p_address: {}",
+            rpc_url,
+            self.ckb_version(),
+            self.p2p_address.as_ref().expect("checked")
+        );
+        Ok(())
     }
 
     pub fn start(&mut self) {
+        self.try_start().unwrap_or_else(|err| {
+            panic!(
+                "failed to start node \"{}\", error: {}",
+                self.node_name(),
+                err
+            )
+        })
+    }
+
+    pub fn try_start(&mut self) -> Result<(), Error> {
+        if self.remote {
+            return self.try_load_remote_info();
+        }
         let binary = &self.node_options.ckb_binary;
+        let working_dir = self.managed_working_dir();
+        let mut args = vec![
+            "-C".to_string(),
+            working_dir.to_string_lossy().to_string(),
+            "run".to_string(),
+            "--ba-advanced".to_string(),
+            "--overwrite-spec".to_string(),
+        ];
+        if self.node_options.indexer {
+            if supports_indexer_flag(binary) {
+                args.push("--indexer".to_string());
+            } else {
+                crate::warn!(
+                    "[Node {}] {} does not support --indexer, run without the indexer",
+                    self.node_name(),
+                    binary.display()
+                );
+            }
+        }
+        args.extend(self.node_options.extra_args.iter().cloned());
+        let stdout = output_stdio(self.node_options.stdout, &working_dir.join("stdout.log"))?;
+        let stderr = output_stdio(self.node_options.stderr, &working_dir.join("stderr.log"))?;
         let mut child_process = Command::new(&binary)
             .env("RUST_BACKTRACE", "full")
-            .args(&[
-                "-C",
-                &self.working_dir().to_string_lossy().to_string(),
-                "run",
-                "--ba-advanced",
-                "--overwrite-spec",
-            ])
+            .envs(self.node_options.envs.iter().cloned())
+            .args(&args)
             .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::inherit())
+            .stdout(stdout)
+            .stderr(stderr)
             .spawn()
-            .unwrap_or_else(|err| {
-                panic!(
-                    "failed to start ckb process, binary: {}, error: {}",
-                    binary.display(),
-                    err
+            .map_err(|err| {
+                io::Error::new(
+                    err.kind(),
+                    format!(
+                        "failed to start ckb process, binary: {}, error: {}",
+                        binary.display(),
+                        err
+                    ),
                 )
-            });
-        let local_node_info = self.wait_for_node_up(&mut child_process);
-        let consensus = self.rpc_client().get_consensus();
-        let genesis_block = self.get_block_by_number(0);
+            })?;
+        let local_node_info = match self.wait_for_node_up(&mut child_process) {
+            Ok(local_node_info) => local_node_info,
+            Err(err) => {
+                if !err.is_node_crashed() {
+                    let _ = child_process.kill();
+                }
+                let _ = child_process.wait();
+                return Err(err);
+            }
+        };
+        *self.crashed.lock().expect("lock crashed") = None;
+        self.detect_ckb_version(&local_node_info);
+        let guard = Watchdog::spawn(child_process, self.post_mortem(), Arc::clone(&self.crashed));
+        let consensus = self.rpc_client().try_get_consensus()?;
+        let genesis_block = self.try_get_block_by_number(0)?;
 
         self.consensus = Some(consensus);
         self.genesis_block = Some(genesis_block);
-        self._guard = Some(ProcessGuard(child_process));
+        self._guard = Some(guard);
         self.node_id = Some(local_node_info.node_id);
-        self.p2p_address = Some(local_node_info.addresses[0].address.clone());
+        self.p2p_address = Some(
+            pick_p2p_address(&local_node_info.addresses, "127.0.0.1").ok_or_else(|| {
+                Error::NotFound(format!(
+                    "p2p listen address of node \"{}\"",
+                    self.node_name()
+                ))
+            })?,
+        );
         crate::info!(
             "[Node {}] START node_id: \"{}\", p2p_address: \"{}\", log_path: \"{}\"",
             self.node_name(),
             self.node_id(),
             self.p2p_address.as_ref().expect("checked"),
-            self.log_path().display()
+            self.managed_log_path().display()
         );
+        Ok(())
     }
 
     pub fn node_name(&self) -> &str {
@@ -229,19 +307,54 @@ impl Node {
         &self.node_options
     }
 
-    pub fn working_dir(&self) -> PathBuf {
+    /// The working dir, `None` for the remote nodes initialized without one
+    pub fn working_dir(&self) -> Option<PathBuf> {
         self.working_dir.clone()
     }
 
-    pub fn log_path(&self) -> PathBuf {
-        self.working_dir().join("data/logs/run.log")
+    pub fn log_path(&self) -> Option<PathBuf> {
+        self.working_dir
+            .as_ref()
+            .map(|working_dir| working_dir.join("data/logs/run.log"))
+    }
+
+    // Only the nodes started by the testkit, which always have a working dir
+    pub(super) fn managed_working_dir(&self) -> PathBuf {
+        self.working_dir
+            .clone()
+            .expect("node started by testkit has working_dir")
+    }
+
+    fn managed_log_path(&self) -> PathBuf {
+        self.managed_working_dir().join("data/logs/run.log")
     }
 
     pub fn rpc_client(&self) -> &RpcClient {
         &self.rpc_client
     }
 
-    /// P2p listen address, without node_id. E.g. "/ip4/0.0.0.0/tcp/9003"
+    /// Return an async RPC client connected to this node. The returned client should be used
+    /// within the tokio runtime where it is going to be awaited.
+    pub fn async_rpc_client(&self) -> AsyncRpcClient {
+        AsyncRpcClient::new(self.rpc_client.url(), self.rpc_client.version())
+    }
+
+    /// The ckb version detected when the node started
+    pub fn ckb_version(&self) -> CkbVersion {
+        self.rpc_client.version()
+    }
+
+    // Recreate the RPC client with the shims of the version reported by the node. Unparsable
+    // versions, e.g. of the development builds, are treated as the latest one.
+    fn detect_ckb_version(&mut self, local_node_info: &LocalNode) {
+        let version = CkbVersion::parse(&local_node_info.version).unwrap_or_default();
+        if version != self.rpc_client.version() {
+            self.rpc_client = RpcClient::new(self.rpc_client.url(), version);
+        }
+    }
+
+    /// P2p listen address reachable from the testkit, without node_id. E.g.
+    /// "/ip4/127.0.0.1/tcp/9003"
     pub fn p2p_address(&self) -> String {
         self.p2p_address.as_ref().unwrap().clone()
     }
@@ -267,49 +380,202 @@ impl Node {
     }
 
     pub fn stop(&mut self) {
+        if let Some(guard) = self._guard.take() {
+            crate::info!(
+                "[Node {}] STOP log_path: {}",
+                self.node_name(),
+                self.managed_log_path().display(),
+            );
+            drop(guard)
+        }
+    }
+
+    /// Send SIGTERM to the ckb process and wait for it to exit within `timeout`, then SIGKILL
+    /// if it is still alive. Return the exit status, or `None` if the node is not running.
+    pub fn stop_gracefully(&mut self, timeout: Duration) -> Option<ExitStatus> {
+        self.try_stop_gracefully(timeout)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_stop_gracefully(&mut self, timeout: Duration) -> Result<Option<ExitStatus>, Error> {
+        let guard = match self._guard.take() {
+            Some(guard) => guard,
+            None => return Ok(None),
+        };
         crate::info!(
-            "[Node {}] STOP log_path: {}",
+            "[Node {}] STOP GRACEFULLY log_path: {}",
             self.node_name(),
-            self.log_path().display(),
+            self.managed_log_path().display(),
         );
-        if self._guard.is_some() {
-            drop(self._guard.take())
+        Ok(Some(guard.terminate(timeout)?))
+    }
+
+    /// Return `Error::NodeCrashed` if the watchdog found that the ckb process exited
+    /// unexpectedly. The post-mortem bundle is located at `post_mortem_dir()`.
+    pub fn ensure_alive(&self) -> Result<(), Error> {
+        match *self.crashed.lock().expect("lock crashed") {
+            Some(status) => Err(Error::NodeCrashed {
+                node_name: self.node_name().to_string(),
+                status,
+                log_path: self.managed_log_path(),
+            }),
+            None => Ok(()),
         }
     }
 
-    fn wait_for_node_up(&self, child_process: &mut Child) -> LocalNode {
+    pub fn is_crashed(&self) -> bool {
+        self.crashed.lock().expect("lock crashed").is_some()
+    }
+
+    /// The directory where the post-mortem bundle is collected when the node crashes. It is
+    /// `$CKB_TESTKIT_POST_MORTEM_DIR/<node_name>-<working_dir_name>` if the environment
+    /// variable is set, otherwise `<working_dir>/post-mortem`. `None` for the remote nodes.
+    pub fn post_mortem_dir(&self) -> Option<PathBuf> {
+        if self.remote {
+            None
+        } else {
+            Some(self.post_mortem().bundle_dir())
+        }
+    }
+
+    fn post_mortem(&self) -> PostMortem {
+        let working_dir = self.managed_working_dir();
+        let stderr_path = if self.node_options.stderr == OutputMode::File {
+            Some(working_dir.join("stderr.log"))
+        } else {
+            None
+        };
+        PostMortem {
+            node_name: self.node_name().to_string(),
+            log_path: self.managed_log_path(),
+            working_dir,
+            stderr_path,
+        }
+    }
+
+    /// Stop the node gracefully and start it again, with the same working dir, ports and node
+    /// id. For the remote nodes, it only reloads the node info.
+    pub fn restart(&mut self) {
+        self.try_restart().unwrap_or_else(|err| {
+            panic!(
+                "failed to restart node \"{}\", error: {}",
+                self.node_name(),
+                err
+            )
+        })
+    }
+
+    pub fn try_restart(&mut self) -> Result<(), Error> {
+        self.try_stop_gracefully(Duration::from_secs(30))?;
+        self.try_start()
+    }
+
+    fn wait_for_node_up(&self, child_process: &mut Child) -> Result<LocalNode, Error> {
         let start_time = Instant::now();
-        while start_time.elapsed() <= Duration::from_secs(60) {
+        while start_time.elapsed() <= self.node_options.startup_timeout {
             if let Ok(local_node_info) = self.rpc_client().inner().local_node_info() {
-                let _x = self.rpc_client().tx_pool_info();
-                return local_node_info;
+                let _x = self.rpc_client().try_tx_pool_info();
+                return Ok(local_node_info);
             }
             match child_process.try_wait() {
                 Ok(None) => sleep(std::time::Duration::from_secs(1)),
                 Ok(Some(status)) => {
+                    let bundle = self.post_mortem().collect(&status);
                     error!(
-                        "{} node crashed, {}, log_path: {}",
+                        "{} node crashed, {}, log_path: {}, post-mortem: {:?}",
                         self.node_name(),
                         status,
-                        self.log_path().display()
+                        self.managed_log_path().display(),
+                        bundle,
                     );
-                    process::exit(status.code().unwrap());
+                    return Err(Error::NodeCrashed {
+                        node_name: self.node_name().to_string(),
+                        status,
+                        log_path: self.managed_log_path(),
+                    });
                 }
                 Err(error) => {
                     error!(
                         "{} node crashed with reason: {}, log_path: {}",
                         self.node_name(),
                         error,
-                        self.log_path().display()
+                        self.managed_log_path().display()
                     );
-                    process::exit(255);
+                    return Err(error.into());
                 }
             }
         }
-        panic!("timeout to start node process")
+        Err(Error::Timeout(format!(
+            "start node \"{}\" process",
+            self.node_name()
+        )))
     }
 }
 
+/// Whether `ckb run` of `binary` accepts `--indexer`. The built-in indexer is added in ckb
+/// 0.106, and the older releases reject the unknown flag.
+fn supports_indexer_flag(binary: &Path) -> bool {
+    Command::new(binary)
+        .args(&["run", "--help"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains("--indexer"))
+        .unwrap_or(false)
+}
+
+/// Pick the p2p address reachable from `host` among the addresses reported by
+/// `local_node_info`, without the "/p2p/<node_id>" suffix.
+///
+/// The address bound to `host` is preferred, then the unspecified address (e.g.
+/// "/ip4/0.0.0.0/tcp/8115") with `host` substituted, and then the one with the highest score.
+fn pick_p2p_address(addresses: &[NodeAddress], host: &str) -> Option<String> {
+    let mut candidates = addresses
+        .iter()
+        .map(|address| {
+            let without_node_id = match address.address.find("/p2p/") {
+                Some(index) => address.address[..index].to_string(),
+                None => address.address.clone(),
+            };
+            (without_node_id, address.score.value())
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let host_protocol = if host.parse::<Ipv4Addr>().is_ok() {
+        "ip4"
+    } else if host.parse::<Ipv6Addr>().is_ok() {
+        "ip6"
+    } else {
+        "dns4"
+    };
+    let host_prefix = format!("/{}/{}/", host_protocol, host);
+    if let Some((address, _)) = candidates
+        .iter()
+        .find(|(address, _)| address.starts_with(&host_prefix))
+    {
+        return Some(address.clone());
+    }
+    for (address, _) in candidates.iter() {
+        for unspecified in &["/ip4/0.0.0.0/", "/ip6/::/"] {
+            if address.starts_with(unspecified) {
+                return Some(address.replacen(unspecified, &host_prefix, 1));
+            }
+        }
+    }
+    candidates.into_iter().next().map(|(address, _)| address)
+}
+
+fn rpc_host(rpc_url: &str) -> Result<String, Error> {
+    let url = Url::parse(rpc_url)
+        .map_err(|err| Error::Other(format!("invalid rpc_url \"{}\", {}", rpc_url, err)))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| Error::Other(format!("rpc_url \"{}\" has no host", rpc_url)))?;
+    Ok(host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string())
+}
+
 fn prepare_working_dir(
     case_name: &str,
     node_options: &NodeOptions,
@@ -318,9 +584,6 @@ fn prepare_working_dir(
 ) -> PathBuf {
     let working_dir: PathBuf = temp_path(&case_name, &node_options.node_name);
     let target_database = &working_dir.join("data/db");
-    let source_database = node_options.initial_database;
-    let source_chain_spec = node_options.chain_spec;
-    let source_app_config = node_options.app_config;
 
     fs::create_dir_all(target_database).unwrap_or_else(|err| {
         panic!(
@@ -329,41 +592,74 @@ fn prepare_working_dir(
             err
         )
     });
+    // An empty initial database means starting from genesis
+    if let Some(ref initial_database) = node_options.initial_database {
+        copy_dir_content(initial_database, target_database);
+    }
+
+    if let Some(ref chain_spec_builder) = node_options.chain_spec_builder {
+        chain_spec_builder
+            .render(&working_dir)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "failed to render chain spec into {}, error: {}",
+                    working_dir.display(),
+                    err
+                )
+            });
+    } else {
+        let chain_spec = node_options
+            .chain_spec
+            .as_ref()
+            .expect("either chain_spec or chain_spec_builder should be set");
+        copy_dir_content(chain_spec, &working_dir);
+    }
+
+    if let Some(ref app_config_builder) = node_options.app_config_builder {
+        app_config_builder
+            .render(&working_dir, rpc_port, p2p_port)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "failed to render ckb.toml into {}, error: {}",
+                    working_dir.display(),
+                    err
+                )
+            });
+    } else {
+        let app_config = node_options
+            .app_config
+            .as_ref()
+            .expect("either app_config or app_config_builder should be set");
+        copy_dir_content(app_config, &working_dir);
+
+        // Modify rpc port and p2p port in ckb.toml
+        let app_config = working_dir.join("ckb.toml");
+        let content = fs::read_to_string(&app_config).unwrap_or_else(|err| {
+            panic!("failed to read {}, error: {}", app_config.display(), err)
+        });
+        let content = content
+            .replace("__RPC_PORT__", &rpc_port.to_string())
+            .replace("__P2P_PORT__", &p2p_port.to_string());
+        fs::write(&app_config, content).unwrap_or_else(|err| {
+            panic!("failed to write {}, error: {}", app_config.display(), err)
+        });
+    }
+
+    working_dir
+}
+
+fn output_stdio(mode: OutputMode, path: &Path) -> Result<Stdio, Error> {
+    match mode {
+        OutputMode::Null => Ok(Stdio::null()),
+        OutputMode::Inherit => Ok(Stdio::inherit()),
+        OutputMode::File => Ok(fs::File::create(path)?.into()),
+    }
+}
+
+fn copy_dir_content(source: &Path, target: &Path) {
     fs_extra::dir::copy(
-        source_database,
-        target_database,
-        &CopyOptions {
-            content_only: true,
-            ..Default::default()
-        },
-    )
-    .unwrap_or_else(|err| {
-        panic!(
-            "failed to copy {} to {}, error: {}",
-            source_database,
-            target_database.display(),
-            err
-        )
-    });
-    fs_extra::dir::copy(
-        source_chain_spec,
-        &working_dir,
-        &CopyOptions {
-            content_only: true,
-            ..Default::default()
-        },
-    )
-    .unwrap_or_else(|err| {
-        panic!(
-            "failed to copy {} to {}, error: {}",
-            source_chain_spec,
-            working_dir.display(),
-            err
-        )
-    });
-    fs_extra::dir::copy(
-        source_app_config,
-        &working_dir,
+        source,
+        target,
         &CopyOptions {
             content_only: true,
             ..Default::default()
@@ -372,21 +668,9 @@ fn prepare_working_dir(
     .unwrap_or_else(|err| {
         panic!(
             "failed to copy {} to {}, error: {}",
-            source_app_config,
-            working_dir.display(),
+            source.display(),
+            target.display(),
             err
         )
     });
-
-    // Modify rpc port and p2p port in ckb.toml
-    let app_config = working_dir.join("ckb.toml");
-    let content = fs::read_to_string(&app_config)
-        .unwrap_or_else(|err| panic!("failed to read {}, error: {}", app_config.display(), err));
-    let content = content
-        .replace("__RPC_PORT__", &rpc_port.to_string())
-        .replace("__P2P_PORT__", &p2p_port.to_string());
-    fs::write(&app_config, content)
-        .unwrap_or_else(|err| panic!("failed to write {}, error: {}", app_config.display(), err));
-
-    working_dir
 }
@@ -1,10 +1,142 @@
+use crate::node::config::{AppConfigBuilder, ChainSpecBuilder};
 use std::path::PathBuf;
+use std::time::Duration;
 
-#[derive(Debug, Clone, Default)]
+/// Where the stdout or stderr of the ckb process goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Discard the output
+    Null,
+    /// Inherit the output of the testkit process
+    Inherit,
+    /// Capture the output into `stdout.log` or `stderr.log` inside the working dir
+    File,
+}
+
+#[derive(Debug, Clone)]
 pub struct NodeOptions {
     pub node_name: String,
     pub ckb_binary: PathBuf,
-    pub initial_database: &'static str,
-    pub chain_spec: &'static str,
-    pub app_config: &'static str,
+    /// The directory copied as the initial database, `None` means starting from genesis
+    pub initial_database: Option<PathBuf>,
+    /// The directory containing the chain spec files, ignored when `chain_spec_builder` is set
+    pub chain_spec: Option<PathBuf>,
+    /// The directory containing ckb.toml, ignored when `app_config_builder` is set
+    pub app_config: Option<PathBuf>,
+    /// Render the chain spec instead of copying the `chain_spec` directory
+    pub chain_spec_builder: Option<ChainSpecBuilder>,
+    /// Render ckb.toml instead of copying the `app_config` directory
+    pub app_config_builder: Option<AppConfigBuilder>,
+    /// Extra arguments appended to `ckb run`
+    pub extra_args: Vec<String>,
+    /// Extra environment variables of the ckb process, e.g. `CKB_LOG`
+    pub envs: Vec<(String, String)>,
+    pub stdout: OutputMode,
+    pub stderr: OutputMode,
+    /// How long to wait for the RPC to be ready after spawning the ckb process
+    pub startup_timeout: Duration,
+    /// Run with the built-in indexer, i.e. `ckb run --indexer`, which the cell lookups of
+    /// `TxBuilder` and `User` rely on. Default is false, since ckb before 0.106 has no built-in
+    /// indexer and rejects the flag; the flag is skipped for such binaries.
+    pub indexer: bool,
+}
+
+impl Default for NodeOptions {
+    fn default() -> Self {
+        Self {
+            node_name: String::new(),
+            ckb_binary: PathBuf::new(),
+            initial_database: None,
+            chain_spec: None,
+            app_config: None,
+            chain_spec_builder: None,
+            app_config_builder: None,
+            extra_args: Vec::new(),
+            envs: Vec::new(),
+            stdout: OutputMode::Null,
+            stderr: OutputMode::Inherit,
+            startup_timeout: Duration::from_secs(60),
+            indexer: false,
+        }
+    }
+}
+
+impl NodeOptions {
+    pub fn new<S: ToString, P: Into<PathBuf>>(node_name: S, ckb_binary: P) -> Self {
+        Self {
+            node_name: node_name.to_string(),
+            ckb_binary: ckb_binary.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn node_name<S: ToString>(mut self, node_name: S) -> Self {
+        self.node_name = node_name.to_string();
+        self
+    }
+
+    pub fn ckb_binary<P: Into<PathBuf>>(mut self, ckb_binary: P) -> Self {
+        self.ckb_binary = ckb_binary.into();
+        self
+    }
+
+    pub fn initial_database<P: Into<PathBuf>>(mut self, initial_database: P) -> Self {
+        self.initial_database = Some(initial_database.into());
+        self
+    }
+
+    pub fn chain_spec<P: Into<PathBuf>>(mut self, chain_spec: P) -> Self {
+        self.chain_spec = Some(chain_spec.into());
+        self
+    }
+
+    pub fn app_config<P: Into<PathBuf>>(mut self, app_config: P) -> Self {
+        self.app_config = Some(app_config.into());
+        self
+    }
+
+    pub fn chain_spec_builder(mut self, chain_spec_builder: ChainSpecBuilder) -> Self {
+        self.chain_spec_builder = Some(chain_spec_builder);
+        self
+    }
+
+    pub fn app_config_builder(mut self, app_config_builder: AppConfigBuilder) -> Self {
+        self.app_config_builder = Some(app_config_builder);
+        self
+    }
+
+    pub fn arg<S: ToString>(mut self, arg: S) -> Self {
+        self.extra_args.push(arg.to_string());
+        self
+    }
+
+    pub fn env<K: ToString, V: ToString>(mut self, key: K, value: V) -> Self {
+        self.envs.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Override the logger filter of ckb.toml via `CKB_LOG`, e.g. "info,ckb-sync=debug"
+    pub fn log_filter<S: ToString>(self, log_filter: S) -> Self {
+        self.env("CKB_LOG", log_filter)
+    }
+
+    pub fn stdout(mut self, stdout: OutputMode) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    pub fn stderr(mut self, stderr: OutputMode) -> Self {
+        self.stderr = stderr;
+        self
+    }
+
+    pub fn startup_timeout(mut self, startup_timeout: Duration) -> Self {
+        self.startup_timeout = startup_timeout;
+        self
+    }
+
+    pub fn indexer(mut self, indexer: bool) -> Self {
+        self.indexer = indexer;
+        self
+    }
 }
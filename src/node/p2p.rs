@@ -1,15 +1,28 @@
 use crate::util::wait_until;
-use crate::Node;
+use crate::{Error, Node};
 
 impl Node {
     pub fn is_p2p_connected(&self, other: &Node) -> bool {
-        self.rpc_client()
-            .get_peers()
+        self.try_is_p2p_connected(other)
+            .expect("rpc call get_peers")
+    }
+
+    pub fn try_is_p2p_connected(&self, other: &Node) -> Result<bool, Error> {
+        self.ensure_alive()?;
+        Ok(self
+            .rpc_client()
+            .try_get_peers()?
             .iter()
-            .any(|peer| &peer.node_id == other.node_id())
+            .any(|peer| &peer.node_id == other.node_id()))
     }
 
     pub fn p2p_connect(&self, other: &Node) {
+        self.try_p2p_connect(other)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_p2p_connect(&self, other: &Node) -> Result<(), Error> {
+        self.ensure_alive()?;
         crate::trace!(
             "Node::p2p_connect(\"{}\", \"{}\") start",
             self.node_name(),
@@ -18,24 +31,33 @@ impl Node {
         let other_node_id = other.node_id().to_string();
         let other_p2p_address = other.p2p_address();
 
-        self.rpc_client().add_node(other_node_id, other_p2p_address);
-        let connected = wait_until(20, || {
-            self.rpc_client()
-                .get_peers()
+        self.rpc_client()
+            .try_add_node(other_node_id, other_p2p_address)?;
+        let mut rpc_error = None;
+        let connected = wait_until(20, || match self.rpc_client().try_get_peers() {
+            Ok(peers) => peers
                 .iter()
-                .any(|remote_node| remote_node.node_id == other.node_id())
+                .any(|remote_node| remote_node.node_id == other.node_id()),
+            Err(err) => {
+                rpc_error = Some(err);
+                true
+            }
         });
+        if let Some(err) = rpc_error {
+            return Err(err);
+        }
         if !connected {
-            panic!(
-                "timeout to connect outbound peer, \
+            return Err(Error::Timeout(format!(
+                "connect outbound peer, \
                 self node name: {}, self p2p address: {}, other node name: {}, other p2p address: {}",
                 self.node_name(),
                 self.p2p_address(),
                 other.node_name(),
                 other.p2p_address(),
-            );
+            )));
         }
         crate::trace!("Node::p2p_connect end");
+        Ok(())
     }
 
     pub fn p2p_connect_uncheck(&self, other: &Node) {
@@ -46,37 +68,61 @@ impl Node {
     }
 
     pub fn p2p_disconnect(&self, other: &Node) {
+        self.try_p2p_disconnect(other)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_p2p_disconnect(&self, other: &Node) -> Result<(), Error> {
+        self.ensure_alive()?;
         let other_node_id = other.node_id().to_string();
 
-        self.rpc_client().remove_node(other_node_id);
+        self.rpc_client().try_remove_node(other_node_id)?;
+        let mut rpc_error = None;
         let disconnected = wait_until(5, || {
-            self.rpc_client()
-                .get_peers()
-                .iter()
-                .all(|remote_node| remote_node.node_id != other.node_id())
-                && other
+            let result = self.rpc_client().try_get_peers().and_then(|self_peers| {
+                other
                     .rpc_client()
-                    .get_peers()
-                    .iter()
-                    .all(|remote_node| remote_node.node_id != self.node_id())
+                    .try_get_peers()
+                    .map(|other_peers| (self_peers, other_peers))
+            });
+            match result {
+                Ok((self_peers, other_peers)) => {
+                    self_peers
+                        .iter()
+                        .all(|remote_node| remote_node.node_id != other.node_id())
+                        && other_peers
+                            .iter()
+                            .all(|remote_node| remote_node.node_id != self.node_id())
+                }
+                Err(err) => {
+                    rpc_error = Some(err);
+                    true
+                }
+            }
         });
+        if let Some(err) = rpc_error {
+            return Err(err);
+        }
         if !disconnected {
-            panic!(
-                "timeout to disconnect peer, \
+            return Err(Error::Timeout(format!(
+                "disconnect peer, \
                 self node name: {}, self node id: {}, other node name: {}, other node id: {}",
                 self.node_name(),
                 self.node_id(),
                 other.node_name(),
                 other.node_id(),
-            );
+            )));
         }
+        Ok(())
     }
 
-    // TODO so confusing
     // workaround for banned address checking (because we are using loopback address)
     // 1. checking banned addresses is empty
     // 2. connecting outbound peer and checking banned addresses is not empty
     // 3. clear banned addresses
+    #[deprecated(
+        note = "drive the misbehavior from a `Connector` and use `Connector::wait_for_ban`"
+    )]
     pub fn p2p_connect_and_wait_ban(&self, other: &Node) {
         let other_node_id = other.node_id().to_string();
         let other_p2p_address = other.p2p_address();
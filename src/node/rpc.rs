@@ -1,4 +1,4 @@
-use crate::Node;
+use crate::{Error, Node};
 use ckb_jsonrpc_types::TxPoolInfo;
 use ckb_types::{
     core::{BlockNumber, BlockView, HeaderView, TransactionView},
@@ -9,12 +9,16 @@ use std::time::{Duration, Instant};
 
 impl Node {
     pub fn submit_block(&self, block: &BlockView) -> Byte32 {
+        self.try_submit_block(block).unwrap()
+    }
+
+    pub fn try_submit_block(&self, block: &BlockView) -> Result<Byte32, Error> {
+        self.ensure_alive()?;
         let hash = self
             .rpc_client()
-            .submit_block("".to_owned(), block.data().into())
-            .unwrap();
-        self.wait_for_tx_pool();
-        hash
+            .try_submit_block("".to_owned(), block.data().into())?;
+        self.try_wait_for_tx_pool()?;
+        Ok(hash)
     }
 
     pub fn submit_transaction(&self, transaction: &TransactionView) -> Byte32 {
@@ -22,99 +26,144 @@ impl Node {
             .send_transaction(transaction.data().into())
     }
 
+    pub fn try_submit_transaction(&self, transaction: &TransactionView) -> Result<Byte32, Error> {
+        self.ensure_alive()?;
+        self.rpc_client()
+            .try_send_transaction(transaction.data().into())
+    }
+
     pub fn get_tip_block(&self) -> BlockView {
+        self.try_get_tip_block().expect("tip block exists")
+    }
+
+    pub fn try_get_tip_block(&self) -> Result<BlockView, Error> {
+        self.ensure_alive()?;
         let rpc_client = self.rpc_client();
-        let tip_number = rpc_client.get_tip_block_number();
+        let tip_number = rpc_client.try_get_tip_block_number()?;
         let block = rpc_client
-            .get_block_by_number(tip_number)
-            .expect("tip block exists");
+            .try_get_block_by_number(tip_number)?
+            .ok_or_else(|| Error::NotFound(format!("tip block {}", tip_number)))?;
         crate::trace!(
             "[Node {}] Node::get_tip_block(), block: {:?}",
             self.node_name(),
             block
         );
-        block.into()
+        Ok(block.into())
     }
 
     pub fn get_tip_block_number(&self) -> BlockNumber {
-        let block_number = self.rpc_client().get_tip_block_number();
+        self.try_get_tip_block_number()
+            .expect("rpc call get_tip_block_number")
+    }
+
+    pub fn try_get_tip_block_number(&self) -> Result<BlockNumber, Error> {
+        self.ensure_alive()?;
+        let block_number = self.rpc_client().try_get_tip_block_number()?;
         crate::trace!(
             "[Node {}] Node::get_tip_block_number(), block_number: {}",
             self.node_name(),
             block_number
         );
-        block_number
+        Ok(block_number)
     }
 
     pub fn get_block(&self, hash: Byte32) -> BlockView {
+        self.try_get_block(hash).expect("block exists")
+    }
+
+    pub fn try_get_block(&self, hash: Byte32) -> Result<BlockView, Error> {
+        self.ensure_alive()?;
         self.rpc_client()
-            .get_block(hash)
-            .expect("block exists")
-            .into()
+            .try_get_block(hash.clone())?
+            .map(Into::into)
+            .ok_or_else(|| Error::NotFound(format!("block {:#x}", hash)))
     }
 
     pub fn get_block_by_number(&self, number: BlockNumber) -> BlockView {
+        self.try_get_block_by_number(number).expect("block exists")
+    }
+
+    pub fn try_get_block_by_number(&self, number: BlockNumber) -> Result<BlockView, Error> {
+        self.ensure_alive()?;
         self.rpc_client()
-            .get_block_by_number(number)
-            .expect("block exists")
-            .into()
+            .try_get_block_by_number(number)?
+            .map(Into::into)
+            .ok_or_else(|| Error::NotFound(format!("block {}", number)))
     }
 
     pub fn get_header_by_number(&self, number: BlockNumber) -> HeaderView {
-        self.rpc_client()
-            .get_header_by_number(number)
+        self.try_get_header_by_number(number)
             .expect("header exists")
-            .into()
+    }
+
+    pub fn try_get_header_by_number(&self, number: BlockNumber) -> Result<HeaderView, Error> {
+        self.ensure_alive()?;
+        self.rpc_client()
+            .try_get_header_by_number(number)?
+            .map(Into::into)
+            .ok_or_else(|| Error::NotFound(format!("header {}", number)))
     }
 
     /// The states of chain and txpool are updated asynchronously. Which means that the chain has
     /// updated to the newest tip but txpool not.
     /// get_tip_tx_pool_info wait to ensure the txpool update to the newest tip as well.
     pub fn get_tip_tx_pool_info(&self) -> TxPoolInfo {
-        let tip_header = self.rpc_client().get_tip_header();
+        self.try_get_tip_tx_pool_info()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_get_tip_tx_pool_info(&self) -> Result<TxPoolInfo, Error> {
+        self.ensure_alive()?;
+        let tip_header = self.rpc_client().try_get_tip_header()?;
         let tip_hash = &tip_header.hash;
         let instant = Instant::now();
         let mut recent = TxPoolInfo::default();
         while instant.elapsed() < Duration::from_secs(10) {
-            let tx_pool_info = self.rpc_client().tx_pool_info();
+            let tx_pool_info = self.rpc_client().try_tx_pool_info()?;
             if &tx_pool_info.tip_hash == tip_hash {
-                return tx_pool_info;
+                return Ok(tx_pool_info);
             }
             recent = tx_pool_info;
         }
-        panic!(
-            "timeout to get_tip_tx_pool_info, tip_header={:?}, tx_pool_info: {:?}",
+        Err(Error::Timeout(format!(
+            "get_tip_tx_pool_info, tip_header={:?}, tx_pool_info: {:?}",
             tip_header, recent
-        );
+        )))
     }
 
     pub fn wait_for_tx_pool(&self) {
+        self.try_wait_for_tx_pool()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_wait_for_tx_pool(&self) -> Result<(), Error> {
+        self.ensure_alive()?;
         let rpc_client = self.rpc_client();
-        let mut chain_tip = rpc_client.get_tip_header();
-        let mut tx_pool_tip = rpc_client.tx_pool_info();
+        let mut chain_tip = rpc_client.try_get_tip_header()?;
+        let mut tx_pool_tip = rpc_client.try_tx_pool_info()?;
         if chain_tip.hash == tx_pool_tip.tip_hash {
-            return;
+            return Ok(());
         }
         let mut instant = Instant::now();
         while instant.elapsed() < Duration::from_secs(10) {
             sleep(std::time::Duration::from_secs(1));
-            chain_tip = rpc_client.get_tip_header();
+            chain_tip = rpc_client.try_get_tip_header()?;
             let prev_tx_pool_tip = tx_pool_tip;
-            tx_pool_tip = rpc_client.tx_pool_info();
+            tx_pool_tip = rpc_client.try_tx_pool_info()?;
             if chain_tip.hash == tx_pool_tip.tip_hash {
-                return;
+                return Ok(());
             } else if prev_tx_pool_tip.tip_hash != tx_pool_tip.tip_hash
                 && tx_pool_tip.tip_number.value() < chain_tip.inner.number.value()
             {
                 instant = Instant::now();
             }
         }
-        panic!(
-            "timeout to wait for tx pool,\n\tchain   tip: {:?}, {:#x},\n\ttx-pool tip: {}, {:#x}",
+        Err(Error::Timeout(format!(
+            "wait for tx pool,\n\tchain   tip: {:?}, {:#x},\n\ttx-pool tip: {}, {:#x}",
             chain_tip.inner.number.value(),
             chain_tip.hash,
             tx_pool_tip.tip_number.value(),
             tx_pool_tip.tip_hash,
-        );
+        )))
     }
 }
@@ -0,0 +1,254 @@
+use crate::{Error, Node, NodeOptions};
+use ckb_types::H256;
+use fs_extra::dir::CopyOptions;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The file inside the snapshot directory recording the content hash
+pub const SNAPSHOT_HASH_FILE: &str = "CONTENT_HASH";
+
+/// The RocksDB files which are rewritten or regenerated without changing the data, e.g. the
+/// manifest is rolled over with a new name when the database is opened
+const NON_DATA_FILES: [&str; 4] = ["IDENTITY", "CURRENT", "LOCK", "LOG"];
+const NON_DATA_FILE_PREFIXES: [&str; 3] = ["MANIFEST-", "OPTIONS-", "LOG."];
+
+/// Return the blake2b hash over the relative paths and contents of the data files inside the
+/// snapshot directory, i.e. the chain spec files and the RocksDB tables and write-ahead logs.
+/// `SNAPSHOT_HASH_FILE` and the RocksDB IDENTITY, CURRENT, LOCK, MANIFEST, OPTIONS and info
+/// log files are excluded.
+pub fn snapshot_content_hash<P: AsRef<Path>>(snapshot_path: P) -> io::Result<H256> {
+    let snapshot_path = snapshot_path.as_ref();
+    let mut files = Vec::new();
+    collect_files(snapshot_path, &mut files)?;
+    files.sort();
+
+    let mut blake2b = ckb_hash::new_blake2b();
+    for file in files {
+        let relative = file
+            .strip_prefix(snapshot_path)
+            .expect("collected from snapshot_path");
+        let name = relative
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if relative == Path::new(SNAPSHOT_HASH_FILE) || !is_data_file(&name) {
+            continue;
+        }
+        let content = fs::read(&file)?;
+        let relative = relative.to_string_lossy();
+        blake2b.update(&(relative.len() as u64).to_le_bytes());
+        blake2b.update(relative.as_bytes());
+        blake2b.update(&(content.len() as u64).to_le_bytes());
+        blake2b.update(&content);
+    }
+    let mut hash = [0u8; 32];
+    blake2b.finalize(&mut hash);
+    Ok(H256::from(hash))
+}
+
+/// Return the content hash recorded by `Node::snapshot`
+pub fn recorded_snapshot_hash<P: AsRef<Path>>(snapshot_path: P) -> io::Result<String> {
+    let content = fs::read_to_string(snapshot_path.as_ref().join(SNAPSHOT_HASH_FILE))?;
+    Ok(content.trim().to_string())
+}
+
+fn is_data_file(name: &str) -> bool {
+    !NON_DATA_FILES.contains(&name)
+        && !NON_DATA_FILE_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn copy_dir_content(source: &Path, target: &Path) -> Result<(), Error> {
+    fs::create_dir_all(target)?;
+    fs_extra::dir::copy(
+        source,
+        target,
+        &CopyOptions {
+            content_only: true,
+            ..Default::default()
+        },
+    )
+    .map(|_| ())
+    .map_err(|err| {
+        Error::Other(format!(
+            "failed to copy {} to {}, error: {}",
+            source.display(),
+            target.display(),
+            err
+        ))
+    })
+}
+
+/// Remove the previous snapshot at `snapshot_path`. Anything else than an empty directory or
+/// a directory containing `SNAPSHOT_HASH_FILE` is kept, and an error is returned.
+fn clear_snapshot_path(snapshot_path: &Path) -> Result<(), Error> {
+    if !snapshot_path.exists() {
+        return Ok(());
+    }
+    let is_empty_dir = snapshot_path.is_dir() && fs::read_dir(snapshot_path)?.next().is_none();
+    if !is_empty_dir && !snapshot_path.join(SNAPSHOT_HASH_FILE).is_file() {
+        return Err(Error::Other(format!(
+            "{} exists and is not a snapshot, refuse to overwrite it",
+            snapshot_path.display()
+        )));
+    }
+    fs::remove_dir_all(snapshot_path)?;
+    Ok(())
+}
+
+/// Copy the database and the chain spec inside `working_dir` into `snapshot_path` and record
+/// the content hash
+fn save_snapshot(working_dir: &Path, snapshot_path: &Path) -> Result<H256, Error> {
+    copy_dir_content(&working_dir.join("data/db"), &snapshot_path.join("db"))?;
+    copy_dir_content(
+        &working_dir.join("specs"),
+        &snapshot_path.join("chain_spec/specs"),
+    )?;
+    let hash = snapshot_content_hash(snapshot_path)?;
+    fs::write(
+        snapshot_path.join(SNAPSHOT_HASH_FILE),
+        format!("{:#x}\n", hash),
+    )?;
+    Ok(hash)
+}
+
+impl Node {
+    /// Save the database and the chain spec of this node into `snapshot_path`, as a fixture
+    /// reusable via `Node::init_from_snapshot`. Return the content hash, which is also recorded
+    /// into `<snapshot_path>/CONTENT_HASH`.
+    ///
+    /// An existing snapshot at `snapshot_path` is replaced, while any other existing non-empty
+    /// path is an error.
+    ///
+    /// The node is stopped gracefully during copying and then restarted, even if copying fails.
+    /// The chain spec is expected under `<working_dir>/specs`, where both the template directories and
+    /// `ChainSpecBuilder` put it.
+    ///
+    /// Layout of the snapshot:
+    ///   - `db/`, the content of `data/db`
+    ///   - `chain_spec/specs/`, the chain spec files
+    pub fn snapshot<P: AsRef<Path>>(&mut self, snapshot_path: P) -> H256 {
+        self.try_snapshot(snapshot_path)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_snapshot<P: AsRef<Path>>(&mut self, snapshot_path: P) -> Result<H256, Error> {
+        let snapshot_path = snapshot_path.as_ref();
+        if self.is_remote() {
+            return Err(Error::Other(format!(
+                "remote node \"{}\" can not be stopped to snapshot",
+                self.node_name()
+            )));
+        }
+        let working_dir = self.managed_working_dir();
+        clear_snapshot_path(snapshot_path)?;
+        let was_running = self.try_stop_gracefully(Duration::from_secs(30))?.is_some();
+
+        // Restart the node even if saving fails, and report the saving error first
+        let saved = save_snapshot(&working_dir, snapshot_path);
+        let restarted = if was_running {
+            self.try_start()
+        } else {
+            Ok(())
+        };
+        let hash = saved?;
+        restarted?;
+        crate::info!(
+            "[Node {}] SNAPSHOT path: {}, content_hash: {:#x}",
+            self.node_name(),
+            snapshot_path.display(),
+            hash
+        );
+        Ok(hash)
+    }
+
+    /// Initialize a node whose database and chain spec are copied from the snapshot saved by
+    /// `Node::snapshot`. `node_options.initial_database` and `node_options.chain_spec` are
+    /// overridden, and `node_options.chain_spec_builder` is ignored.
+    ///
+    /// The content hash of the snapshot is verified against the recorded one first.
+    pub fn init_from_snapshot<S: ToString, P: AsRef<Path>>(
+        case_name: S,
+        snapshot_path: P,
+        node_options: NodeOptions,
+    ) -> Self {
+        Self::try_init_from_snapshot(case_name, snapshot_path, node_options)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_init_from_snapshot<S: ToString, P: AsRef<Path>>(
+        case_name: S,
+        snapshot_path: P,
+        node_options: NodeOptions,
+    ) -> Result<Self, Error> {
+        let snapshot_path = snapshot_path.as_ref();
+        let recorded_hash = recorded_snapshot_hash(snapshot_path)?;
+        let content_hash = format!("{:#x}", snapshot_content_hash(snapshot_path)?);
+        if recorded_hash != content_hash {
+            return Err(Error::Other(format!(
+                "snapshot {} is modified, recorded content hash: {}, actual: {}",
+                snapshot_path.display(),
+                recorded_hash,
+                content_hash
+            )));
+        }
+        let node_options = NodeOptions {
+            initial_database: Some(snapshot_path.join("db")),
+            chain_spec: Some(snapshot_path.join("chain_spec")),
+            chain_spec_builder: None,
+            ..node_options
+        };
+        Ok(Node::init(case_name, node_options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clear_snapshot_path, SNAPSHOT_HASH_FILE};
+    use std::fs;
+
+    #[test]
+    fn test_clear_snapshot_path() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let missing = tempdir.path().join("missing");
+        assert!(clear_snapshot_path(&missing).is_ok());
+
+        let empty = tempdir.path().join("empty");
+        fs::create_dir(&empty).unwrap();
+        assert!(clear_snapshot_path(&empty).is_ok());
+        assert!(!empty.exists());
+
+        let snapshot = tempdir.path().join("snapshot");
+        fs::create_dir_all(snapshot.join("db")).unwrap();
+        fs::write(snapshot.join(SNAPSHOT_HASH_FILE), "0x00\n").unwrap();
+        assert!(clear_snapshot_path(&snapshot).is_ok());
+        assert!(!snapshot.exists());
+
+        let other = tempdir.path().join("other");
+        fs::create_dir(&other).unwrap();
+        fs::write(other.join("data"), "data").unwrap();
+        assert!(clear_snapshot_path(&other).is_err());
+        assert!(other.join("data").exists());
+
+        let file = tempdir.path().join("file");
+        fs::write(&file, "data").unwrap();
+        assert!(clear_snapshot_path(&file).is_err());
+        assert!(file.exists());
+    }
+}
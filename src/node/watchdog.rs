@@ -0,0 +1,212 @@
+use crate::error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// The environment variable specifying where to collect the post-mortem bundles, e.g. the CI
+/// artifacts directory. Default is `<working_dir>/post-mortem`.
+pub const POST_MORTEM_DIR_ENV: &str = "CKB_TESTKIT_POST_MORTEM_DIR";
+
+/// The number of the last lines of run.log kept in the post-mortem bundle
+const LOG_TAIL_LINES: usize = 1000;
+
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The files collected into the post-mortem bundle when the ckb process exits unexpectedly.
+#[derive(Debug, Clone)]
+pub(super) struct PostMortem {
+    pub(super) node_name: String,
+    pub(super) working_dir: PathBuf,
+    pub(super) log_path: PathBuf,
+    pub(super) stderr_path: Option<PathBuf>,
+}
+
+impl PostMortem {
+    /// Return the directory of the post-mortem bundle
+    pub(super) fn bundle_dir(&self) -> PathBuf {
+        match ::std::env::var(POST_MORTEM_DIR_ENV) {
+            Ok(dir) => PathBuf::from(dir).join(format!(
+                "{}-{}",
+                self.node_name,
+                self.working_dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            )),
+            Err(_) => self.working_dir.join("post-mortem"),
+        }
+    }
+
+    /// Collect the bundle: the exit status, the tail of run.log, the captured stderr, ckb.toml
+    /// and the chain specs.
+    pub(super) fn collect(&self, status: &ExitStatus) -> io::Result<PathBuf> {
+        let bundle_dir = self.bundle_dir();
+        fs::create_dir_all(&bundle_dir)?;
+        fs::write(bundle_dir.join("exit_status"), format!("{}\n", status))?;
+        if let Ok(log) = fs::read_to_string(&self.log_path) {
+            let lines = log.lines().collect::<Vec<_>>();
+            let tail = &lines[lines.len().saturating_sub(LOG_TAIL_LINES)..];
+            fs::write(bundle_dir.join("run.log.tail"), tail.join("\n"))?;
+        }
+        if let Some(ref stderr_path) = self.stderr_path {
+            copy_if_exists(stderr_path, &bundle_dir.join("stderr.log"))?;
+        }
+        copy_if_exists(
+            &self.working_dir.join("ckb.toml"),
+            &bundle_dir.join("ckb.toml"),
+        )?;
+        let specs_dir = self.working_dir.join("specs");
+        if specs_dir.is_dir() {
+            for entry in fs::read_dir(specs_dir)? {
+                let path = entry?.path();
+                if path.is_file() {
+                    copy_if_exists(&path, &bundle_dir.join(path.file_name().unwrap()))?;
+                }
+            }
+        }
+        Ok(bundle_dir)
+    }
+}
+
+fn copy_if_exists(source: &Path, target: &Path) -> io::Result<()> {
+    if source.exists() {
+        fs::copy(source, target)?;
+    }
+    Ok(())
+}
+
+/// Own the ckb process and watch it in a background thread. An unexpected exit is recorded
+/// into `crashed` and a post-mortem bundle is collected. Dropping the watchdog kills the
+/// process.
+pub(super) struct Watchdog {
+    child: Arc<Mutex<Child>>,
+    crashed: Arc<Mutex<Option<ExitStatus>>>,
+    stopping: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    pub(super) fn spawn(
+        child: Child,
+        post_mortem: PostMortem,
+        crashed: Arc<Mutex<Option<ExitStatus>>>,
+    ) -> Self {
+        let child = Arc::new(Mutex::new(child));
+        let stopping = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let child = Arc::clone(&child);
+            let crashed = Arc::clone(&crashed);
+            let stopping = Arc::clone(&stopping);
+            thread::Builder::new()
+                .name(format!("watchdog-{}", post_mortem.node_name))
+                .spawn(move || loop {
+                    thread::sleep(WATCH_INTERVAL);
+                    if stopping.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    let status = match child.lock().expect("lock child").try_wait() {
+                        Ok(Some(status)) => status,
+                        Ok(None) => continue,
+                        Err(err) => {
+                            error!(
+                                "[Node {}] watchdog failed to wait ckb process, error: {}",
+                                post_mortem.node_name, err
+                            );
+                            return;
+                        }
+                    };
+                    if stopping.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    let bundle = post_mortem.collect(&status);
+                    error!(
+                        "[Node {}] node crashed, {}, log_path: {}, post-mortem: {:?}",
+                        post_mortem.node_name,
+                        status,
+                        post_mortem.log_path.display(),
+                        bundle,
+                    );
+                    *crashed.lock().expect("lock crashed") = Some(status);
+                    return;
+                })
+                .expect("spawn watchdog thread")
+        };
+        Self {
+            child,
+            crashed,
+            stopping,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop watching, so that the following exit is not treated as a crash
+    fn stop_watching(&mut self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Send SIGTERM to the process and wait for it to exit within `timeout`, then kill it.
+    /// Return the recorded exit status directly if the process has already exited, as its pid
+    /// may be reused after being reaped.
+    pub(super) fn terminate(mut self, timeout: Duration) -> io::Result<ExitStatus> {
+        self.stop_watching();
+        if let Some(status) = *self.crashed.lock().expect("lock crashed") {
+            return Ok(status);
+        }
+        let mut child = self.child.lock().expect("lock child");
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        terminate(&mut child)?;
+        let start_time = Instant::now();
+        while start_time.elapsed() <= timeout {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        error!(
+            "ckb process {} not exited within {:?} after SIGTERM, kill it",
+            child.id(),
+            timeout
+        );
+        child.kill()?;
+        child.wait()
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.stop_watching();
+        let mut child = self.child.lock().expect("lock child");
+        if let Ok(None) = child.try_wait() {
+            let _x = child
+                .kill()
+                .map_err(|err| error!("failed to kill ckb process, error: {}", err));
+        }
+        let _y = child.wait();
+    }
+}
+
+#[cfg(unix)]
+fn terminate(child: &mut Child) -> io::Result<()> {
+    let ret = unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+// There is no SIGTERM on non-unix platforms, fall back to kill
+#[cfg(not(unix))]
+fn terminate(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
@@ -1,5 +1,5 @@
 use crate::util::wait_until;
-use crate::Nodes;
+use crate::{Error, Nodes};
 use ckb_types::{
     core::{BlockNumber, HeaderView},
     packed::Byte32,
@@ -20,10 +20,13 @@ impl Nodes {
         };
 
         // 60 seconds is a reasonable timeout to sync, even for poor CI server
+        let highest_hashes = highest_hashes.into_iter().collect::<Vec<_>>();
         let synced = wait_until(60, || {
-            highest_hashes.iter().all(|hash| {
-                self.nodes()
-                    .all(|node| node.rpc_client().get_header(hash.clone()).is_some())
+            self.nodes().all(|node| {
+                node.rpc_client()
+                    .get_headers(highest_hashes.clone())
+                    .iter()
+                    .all(Option::is_some)
             })
         });
 
@@ -44,21 +47,110 @@ impl Nodes {
         Ok(())
     }
 
+    pub fn try_waiting_for_sync(&self) -> Result<(), Error> {
+        crate::trace!("Nodes::try_waiting_for_sync start");
+        let highest_hashes: HashSet<_> = {
+            let tip_blocks = self
+                .nodes()
+                .map(|node| node.try_get_tip_block())
+                .collect::<Result<HashSet<_>, _>>()?;
+            let tip_numbers = tip_blocks.iter().map(|block| block.number());
+            let highest_number = tip_numbers
+                .max()
+                .ok_or_else(|| Error::Other("no nodes".to_string()))?;
+            let highest_blocks = tip_blocks
+                .into_iter()
+                .filter(|block| block.number() == highest_number);
+            highest_blocks.map(|block| block.hash()).collect()
+        };
+
+        // 60 seconds is a reasonable timeout to sync, even for poor CI server
+        let highest_hashes = highest_hashes.into_iter().collect::<Vec<_>>();
+        let mut rpc_error = None;
+        let synced = wait_until(60, || {
+            let result = self.nodes().try_fold(true, |synced, node| {
+                node.rpc_client()
+                    .try_get_headers(highest_hashes.clone())
+                    .map(|headers| synced && headers.iter().all(Option::is_some))
+            });
+            match result {
+                Ok(synced) => synced,
+                Err(err) => {
+                    rpc_error = Some(err);
+                    true
+                }
+            }
+        });
+        if let Some(err) = rpc_error {
+            return Err(err);
+        }
+
+        if !synced {
+            let mut tips = Vec::new();
+            for node in self.nodes() {
+                let block = node.try_get_tip_block()?;
+                tips.push(format!(
+                    "(\"{}\", {}, {:#x})",
+                    node.node_name(),
+                    block.number(),
+                    block.hash()
+                ));
+            }
+            return Err(Error::Timeout(format!(
+                "waiting for sync, tips: [{}]",
+                tips.join(", ")
+            )));
+        }
+        for node in self.nodes() {
+            node.try_wait_for_tx_pool()?;
+        }
+        crate::trace!("Nodes::try_waiting_for_sync end");
+        Ok(())
+    }
+
     pub fn get_fixed_header(&self) -> HeaderView {
+        self.try_get_fixed_header()
+            .expect("rpc call get_fixed_header")
+    }
+
+    pub fn try_get_fixed_header(&self) -> Result<HeaderView, Error> {
+        // The number of headers fetched within one batch request
+        const SCAN_BATCH_SIZE: BlockNumber = 100;
+
         let maximal_number = self
             .nodes()
-            .map(|node| node.get_tip_block_number())
+            .map(|node| node.try_get_tip_block_number())
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
             .min()
-            .expect("at least 1 node");
-        for number in (0..=maximal_number).rev() {
-            let headers = self
+            .ok_or_else(|| Error::Other("no nodes".to_string()))?;
+        let mut end_number = maximal_number;
+        loop {
+            let start_number = end_number.saturating_sub(SCAN_BATCH_SIZE - 1);
+            let headers_of_nodes = self
                 .nodes()
-                .map(|node| node.get_header_by_number(number))
-                .collect::<HashSet<_>>();
-            if headers.len() == 1 {
-                return headers.into_iter().collect::<Vec<_>>()[0].to_owned();
+                .map(|node| {
+                    node.rpc_client()
+                        .try_get_headers_by_number(start_number..=end_number)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            for offset in (0..=(end_number - start_number) as usize).rev() {
+                let hashes = headers_of_nodes
+                    .iter()
+                    .map(|headers| headers[offset].as_ref().map(|header| header.hash.clone()))
+                    .collect::<HashSet<_>>();
+                if hashes.len() == 1 {
+                    if let Some(header) = headers_of_nodes[0][offset].clone() {
+                        return Ok(header.into());
+                    }
+                }
+            }
+            if start_number == 0 {
+                return Err(Error::Other(
+                    "no fixed header, the nodes have different genesis blocks".to_string(),
+                ));
             }
+            end_number = start_number - 1;
         }
-        unreachable!()
     }
 }
@@ -0,0 +1,398 @@
+use super::p2p::try_connect_pair;
+use crate::util::wait_until;
+use crate::{Error, Node, Nodes};
+use ckb_jsonrpc_types::Status;
+use ckb_types::{
+    core::{BlockNumber, HeaderView, TransactionView},
+    packed::Byte32,
+};
+
+/// The number of blocks a transaction needs to get committed: proposed in the first one and
+/// committed in the third one
+const PROPOSAL_WINDOW_BLOCKS: u64 = 3;
+
+/// One side of a fork: the nodes growing it, the number of blocks mined on top of the common
+/// ancestor and the transactions sent to it.
+///
+/// The blocks are mined by the first node of `node_names`. A transaction needs at least 3
+/// blocks (the proposal window) to get committed, so a branch with transactions must be at
+/// least 3 blocks long.
+#[derive(Debug, Clone)]
+pub struct Branch {
+    node_names: Vec<String>,
+    length: u64,
+    transactions: Vec<TransactionView>,
+}
+
+impl Branch {
+    pub fn new<S: ToString>(node_names: &[S], length: u64) -> Self {
+        Self {
+            node_names: node_names.iter().map(ToString::to_string).collect(),
+            length,
+            transactions: Vec::new(),
+        }
+    }
+
+    pub fn transaction(mut self, transaction: TransactionView) -> Self {
+        self.transactions.push(transaction);
+        self
+    }
+
+    pub fn transactions(mut self, transactions: Vec<TransactionView>) -> Self {
+        self.transactions.extend(transactions);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForkSide {
+    A,
+    B,
+}
+
+/// What happened after the two branches of `Nodes::fork` met
+#[derive(Debug, Clone)]
+pub struct ForkReport {
+    pub ancestor: HeaderView,
+    pub tip_a: HeaderView,
+    pub tip_b: HeaderView,
+    /// The branch on the converged main chain
+    pub winner: ForkSide,
+    /// The tip all nodes converged to
+    pub final_tip: HeaderView,
+    /// The transactions committed on the losing branch but not on the main chain
+    pub detached_transactions: Vec<Byte32>,
+    /// The detached transactions which were re-added into the tx-pool of the winner's miner
+    pub readded_transactions: Vec<Byte32>,
+}
+
+impl ForkReport {
+    pub fn assert_winner(&self, expected: ForkSide) {
+        assert_eq!(
+            self.winner, expected,
+            "unexpected fork winner, report: {:?}",
+            self
+        );
+    }
+
+    pub fn assert_final_tip(&self, expected: &Byte32) {
+        assert_eq!(
+            &self.final_tip.hash(),
+            expected,
+            "unexpected converged tip, report: {:?}",
+            self
+        );
+    }
+}
+
+impl Nodes {
+    /// Build competing chains on top of the common `ancestor` and let them meet:
+    ///
+    /// 1. truncate all nodes to `ancestor`
+    /// 2. partition the nodes into the ones of `branch_a` and the ones of `branch_b`
+    /// 3. on each side, send the branch transactions and mine the branch blocks
+    /// 4. heal the partition and wait for all nodes to converge to the same tip
+    ///
+    /// Every node should belong to exactly one branch, and the branches should be separable as
+    /// described in `Nodes::partition`. Both are checked before truncating any node.
+    pub fn fork(
+        &mut self,
+        ancestor: BlockNumber,
+        branch_a: Branch,
+        branch_b: Branch,
+    ) -> ForkReport {
+        self.try_fork(ancestor, branch_a, branch_b)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_fork(
+        &mut self,
+        ancestor: BlockNumber,
+        branch_a: Branch,
+        branch_b: Branch,
+    ) -> Result<ForkReport, Error> {
+        let miner_a = branch_miner(&branch_a)?;
+        let miner_b = branch_miner(&branch_b)?;
+        ensure_branch_commits(&branch_a)?;
+        ensure_branch_commits(&branch_b)?;
+        let group_a = branch_a
+            .node_names
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let group_b = branch_b
+            .node_names
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        // Check the partition before truncating, so that a bad setup leaves the chains as is
+        self.try_check_partition(&[&group_a, &group_b])?;
+
+        self.try_waiting_for_sync()?;
+        let ancestor = self
+            .try_get_node(miner_a)?
+            .try_get_header_by_number(ancestor)?;
+        for node in self.nodes() {
+            let hash = node.rpc_client().try_get_block_hash(ancestor.number())?;
+            if hash.as_ref() != Some(&ancestor.hash()) {
+                return Err(Error::Other(format!(
+                    "node \"{}\" does not have the ancestor {} {:#x}, its block hash: {:?}",
+                    node.node_name(),
+                    ancestor.number(),
+                    ancestor.hash(),
+                    hash
+                )));
+            }
+        }
+        for node in self.nodes() {
+            if node.try_get_tip_block_number()? > ancestor.number() {
+                node.rpc_client().try_truncate(ancestor.hash())?;
+                node.try_wait_for_tx_pool()?;
+            }
+        }
+        crate::info!(
+            "[Nodes] FORK at {} {:#x}, branch_a: {:?}, branch_b: {:?}",
+            ancestor.number(),
+            ancestor.hash(),
+            branch_a.node_names,
+            branch_b.node_names
+        );
+
+        self.try_partition(&[&group_a, &group_b])?;
+        let (tip_a, committed_a) = self.try_grow_branch(&branch_a)?;
+        let (tip_b, committed_b) = self.try_grow_branch(&branch_b)?;
+        let diverged = self
+            .try_get_node(miner_b)?
+            .rpc_client()
+            .try_get_block_hash(tip_a.number())?
+            .as_ref()
+            != Some(&tip_a.hash());
+        if !diverged {
+            self.try_heal()?;
+            return Err(Error::Other(format!(
+                "the branches did not diverge, the chain of branch_b tip {} {:#x} contains \
+                 branch_a tip {} {:#x}",
+                tip_b.number(),
+                tip_b.hash(),
+                tip_a.number(),
+                tip_a.hash()
+            )));
+        }
+
+        self.try_heal()?;
+        let final_tip = self.try_wait_for_convergence()?;
+
+        let winner = if self
+            .try_get_node(miner_a)?
+            .rpc_client()
+            .try_get_block_hash(tip_a.number())?
+            .as_ref()
+            == Some(&tip_a.hash())
+        {
+            ForkSide::A
+        } else {
+            ForkSide::B
+        };
+        let (losing_committed, winner_miner) = match winner {
+            ForkSide::A => (committed_b, miner_a),
+            ForkSide::B => (committed_a, miner_b),
+        };
+        let winner_node = self.try_get_node(winner_miner)?;
+
+        let mut detached_transactions = Vec::new();
+        let mut readded_transactions = Vec::new();
+        for hash in losing_committed {
+            let status = winner_node
+                .rpc_client()
+                .try_get_transaction(hash.clone())?
+                .map(|transaction| transaction.tx_status.status);
+            match status {
+                Some(Status::Committed) => continue,
+                Some(Status::Pending) | Some(Status::Proposed) => {
+                    readded_transactions.push(hash.clone());
+                }
+                _ => {}
+            }
+            detached_transactions.push(hash);
+        }
+
+        let report = ForkReport {
+            ancestor,
+            tip_a,
+            tip_b,
+            winner,
+            final_tip,
+            detached_transactions,
+            readded_transactions,
+        };
+        crate::info!("[Nodes] FORK report: {:?}", report);
+        Ok(report)
+    }
+
+    /// Wait until all nodes have the same tip, then return the tip
+    pub fn wait_for_convergence(&self) -> HeaderView {
+        self.try_wait_for_convergence()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_wait_for_convergence(&self) -> Result<HeaderView, Error> {
+        self.try_waiting_for_sync()?;
+        let mut rpc_error = None;
+        let converged = wait_until(60, || {
+            let result = self
+                .nodes()
+                .map(|node| node.rpc_client().try_get_tip_header())
+                .collect::<Result<Vec<_>, _>>();
+            match result {
+                Ok(tips) => tips.windows(2).all(|pair| pair[0].hash == pair[1].hash),
+                Err(err) => {
+                    rpc_error = Some(err);
+                    true
+                }
+            }
+        });
+        if let Some(err) = rpc_error {
+            return Err(err);
+        }
+        let mut tips = Vec::new();
+        for node in self.nodes() {
+            let tip: HeaderView = node.rpc_client().try_get_tip_header()?.into();
+            tips.push((node.node_name().to_string(), tip));
+        }
+        if !converged {
+            let tips = tips
+                .iter()
+                .map(|(node_name, tip)| {
+                    format!("(\"{}\", {}, {:#x})", node_name, tip.number(), tip.hash())
+                })
+                .collect::<Vec<_>>();
+            return Err(Error::Timeout(format!(
+                "waiting for convergence, tips: [{}]",
+                tips.join(", ")
+            )));
+        }
+        for node in self.nodes() {
+            node.try_wait_for_tx_pool()?;
+        }
+        Ok(tips.pop().map(|(_, tip)| tip).expect("at least 1 node"))
+    }
+
+    // Send the transactions and mine the blocks of `branch` on its miner, then wait for the
+    // other nodes of the branch to sync. Return the branch tip and the branch transactions
+    // committed on it.
+    fn try_grow_branch(&self, branch: &Branch) -> Result<(HeaderView, Vec<Byte32>), Error> {
+        let miner = self.try_get_node(branch_miner(branch)?)?;
+        for transaction in branch.transactions.iter() {
+            miner.try_submit_transaction(transaction)?;
+        }
+        miner.try_mine(branch.length)?;
+        let tip: HeaderView = miner.rpc_client().try_get_tip_header()?.into();
+        let mut committed = Vec::new();
+        for transaction in branch.transactions.iter() {
+            let status = miner
+                .rpc_client()
+                .try_get_transaction(transaction.hash())?
+                .map(|transaction| transaction.tx_status.status);
+            if status == Some(Status::Committed) {
+                committed.push(transaction.hash());
+            }
+        }
+
+        for node_name in branch.node_names.iter().skip(1) {
+            let node = self.try_get_node(node_name)?;
+            try_connect_pair(miner, node)?;
+            try_wait_for_header(node, &tip.hash())?;
+        }
+        Ok((tip, committed))
+    }
+}
+
+fn branch_miner(branch: &Branch) -> Result<&str, Error> {
+    branch
+        .node_names
+        .first()
+        .map(String::as_str)
+        .ok_or_else(|| Error::Other("branch without nodes".to_string()))
+}
+
+fn ensure_branch_commits(branch: &Branch) -> Result<(), Error> {
+    if !branch.transactions.is_empty() && branch.length < PROPOSAL_WINDOW_BLOCKS {
+        return Err(Error::Other(format!(
+            "branch {:?} with transactions should be at least {} blocks long, got {}",
+            branch.node_names, PROPOSAL_WINDOW_BLOCKS, branch.length
+        )));
+    }
+    Ok(())
+}
+
+fn try_wait_for_header(node: &Node, hash: &Byte32) -> Result<(), Error> {
+    let mut rpc_error = None;
+    let synced = wait_until(60, || {
+        match node.rpc_client().try_get_header(hash.clone()) {
+            Ok(header) => header.is_some(),
+            Err(err) => {
+                rpc_error = Some(err);
+                true
+            }
+        }
+    });
+    if let Some(err) = rpc_error {
+        return Err(err);
+    }
+    if !synced {
+        return Err(Error::Timeout(format!(
+            "node \"{}\" syncing header {:#x}",
+            node.node_name(),
+            hash
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Branch;
+    use crate::{AppConfigBuilder, ChainSpecBuilder, NodeOptions, Nodes};
+    use std::env;
+
+    // Requires a ckb binary at `$CKB_BIN` and the always-success script binary at
+    // `$ALWAYS_SUCCESS_CELL`
+    #[test]
+    #[ignore]
+    fn test_fork_default_local_nodes() {
+        let ckb_binary = env::var("CKB_BIN").expect("CKB_BIN");
+        let always_success_cell = env::var("ALWAYS_SUCCESS_CELL").expect("ALWAYS_SUCCESS_CELL");
+        let node_options = |node_name: &str| {
+            NodeOptions::new(node_name, &ckb_binary)
+                .chain_spec_builder(ChainSpecBuilder::new(&always_success_cell))
+                .app_config_builder(AppConfigBuilder::new())
+        };
+        let mut nodes = Nodes::init(
+            "test_fork_default_local_nodes",
+            vec![
+                (&ckb_binary, node_options("node_a")),
+                (&ckb_binary, node_options("node_b")),
+            ],
+        );
+        nodes.start();
+        nodes.p2p_connect();
+        nodes.get_node("node_a").mine(5);
+        nodes.waiting_for_sync().expect("nodes should be synced");
+        let tips = nodes
+            .nodes()
+            .map(|node| (node.node_name().to_string(), node.get_tip_block()))
+            .collect::<Vec<_>>();
+
+        let result = nodes.try_fork(1, Branch::new(&["node_a"], 2), Branch::new(&["node_b"], 3));
+        let err = result.expect_err("the default local nodes are not separable");
+        assert!(err.to_string().contains("share IP"), "{}", err);
+        for (node_name, tip) in tips {
+            assert_eq!(
+                nodes.get_node(&node_name).get_tip_block().hash(),
+                tip.hash(),
+                "node \"{}\" was truncated",
+                node_name
+            );
+        }
+    }
+}
@@ -1,5 +1,9 @@
 mod chain;
+mod fork;
 mod nodes;
 mod p2p;
+mod topology;
 
+pub use fork::{Branch, ForkReport, ForkSide};
 pub use nodes::Nodes;
+pub use topology::PARTITION_BAN_REASON;
@@ -1,14 +1,20 @@
-use crate::Node;
+use crate::{Error, Node, NodeOptions};
 use std::collections::hash_map::{Keys, Values};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 pub struct Nodes {
     _inner: HashMap<String, Node>,
+    // The connections cut by `partition`, as (outbound, inbound) node names
+    pub(super) cut_edges: Vec<(String, String)>,
 }
 
 impl From<HashMap<String, Node>> for Nodes {
     fn from(nodes: HashMap<String, Node>) -> Self {
-        Nodes { _inner: nodes }
+        Nodes {
+            _inner: nodes,
+            cut_edges: Vec::new(),
+        }
     }
 }
 
@@ -35,6 +41,37 @@ impl AsRef<HashMap<String, Node>> for Nodes {
 }
 
 impl Nodes {
+    /// Initialize the nodes of `case_name` from `(ckb_binary, node_options)` pairs, so that one
+    /// network can mix several ckb releases, e.g. for upgrade or compatibility testing. The ckb
+    /// version of each node is detected when it starts.
+    pub fn init<S: ToString, P: Into<PathBuf>>(case_name: S, nodes: Vec<(P, NodeOptions)>) -> Self {
+        let case_name = case_name.to_string();
+        nodes
+            .into_iter()
+            .map(|(ckb_binary, node_options)| {
+                Node::init(&case_name, node_options.ckb_binary(ckb_binary))
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    pub fn start(&mut self) {
+        self.try_start().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_start(&mut self) -> Result<(), Error> {
+        for node in self._inner.values_mut() {
+            node.try_start()?;
+        }
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        for node in self._inner.values_mut() {
+            node.stop();
+        }
+    }
+
     pub fn get_node(&self, node_name: &str) -> &Node {
         assert!(self._inner.contains_key(node_name));
         self._inner.get(node_name).expect("checked above")
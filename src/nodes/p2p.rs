@@ -1,30 +1,52 @@
-use crate::Nodes;
+use crate::{Error, Node, Nodes};
 
 impl Nodes {
     pub fn p2p_connect(&self) {
+        self.try_p2p_connect()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_p2p_connect(&self) -> Result<(), Error> {
         for node_a in self.nodes() {
             for node_b in self.nodes() {
-                if node_a.p2p_address() != node_b.p2p_address() && !node_a.is_p2p_connected(node_b)
-                {
-                    if node_a.get_tip_block_number() < node_b.get_tip_block_number() {
-                        // An ibd node will not request GetHeaders from inbound peers.
-                        // https://github.com/nervosnetwork/ckb/blob/78fb281317aeaaa8b2621908cda79928ac697df4/sync/src/synchronizer/mod.rs#L543
-                        node_a.p2p_connect(node_b);
-                    } else {
-                        node_b.p2p_connect(node_a);
-                    }
+                if node_a.p2p_address() != node_b.p2p_address() {
+                    try_connect_pair(node_a, node_b)?;
                 }
             }
         }
+        Ok(())
     }
 
     pub fn p2p_disconnect(&self) {
+        self.try_p2p_disconnect()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_p2p_disconnect(&self) -> Result<(), Error> {
         for node_a in self.nodes() {
             for node_b in self.nodes() {
-                if node_a.p2p_address() != node_b.p2p_address() && node_a.is_p2p_connected(node_b) {
-                    node_a.p2p_disconnect(node_b);
+                if node_a.p2p_address() != node_b.p2p_address()
+                    && node_a.try_is_p2p_connected(node_b)?
+                {
+                    node_a.try_p2p_disconnect(node_b)?;
                 }
             }
         }
+        Ok(())
+    }
+}
+
+/// Connect `node_a` and `node_b` unless they are connected already. The node with the lower
+/// tip dials the other.
+pub(super) fn try_connect_pair(node_a: &Node, node_b: &Node) -> Result<(), Error> {
+    if node_a.try_is_p2p_connected(node_b)? {
+        return Ok(());
+    }
+    if node_a.try_get_tip_block_number()? < node_b.try_get_tip_block_number()? {
+        // An ibd node will not request GetHeaders from inbound peers.
+        // https://github.com/nervosnetwork/ckb/blob/78fb281317aeaaa8b2621908cda79928ac697df4/sync/src/synchronizer/mod.rs#L543
+        node_a.try_p2p_connect(node_b)
+    } else {
+        node_b.try_p2p_connect(node_a)
     }
 }
@@ -0,0 +1,347 @@
+use super::p2p::try_connect_pair;
+use crate::util::p2p_ip;
+use crate::{Error, Node, Nodes};
+use ckb_jsonrpc_types::Timestamp;
+use std::collections::HashSet;
+use std::fs;
+
+/// The reason of the bans inserted by `Nodes::partition`, so that `Nodes::heal` only deletes
+/// its own bans.
+pub const PARTITION_BAN_REASON: &str = "ckb-testkit partition";
+
+/// How long the partition bans last, in milliseconds
+const PARTITION_BAN_TIME: u64 = 24 * 60 * 60 * 1000;
+
+impl Nodes {
+    /// Connect `node_names[0] - node_names[1] - ... - node_names[n-1]`
+    pub fn p2p_connect_line(&self, node_names: &[&str]) {
+        self.try_p2p_connect_line(node_names)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_p2p_connect_line(&self, node_names: &[&str]) -> Result<(), Error> {
+        let edges = node_names
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect::<Vec<_>>();
+        self.try_p2p_connect_edges(&edges)
+    }
+
+    /// Connect the line `node_names` and then the last node with the first one
+    pub fn p2p_connect_ring(&self, node_names: &[&str]) {
+        self.try_p2p_connect_ring(node_names)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_p2p_connect_ring(&self, node_names: &[&str]) -> Result<(), Error> {
+        let mut edges = node_names
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect::<Vec<_>>();
+        if node_names.len() > 2 {
+            edges.push((node_names[node_names.len() - 1], node_names[0]));
+        }
+        self.try_p2p_connect_edges(&edges)
+    }
+
+    /// Connect `hub` with every other node
+    pub fn p2p_connect_star(&self, hub: &str) {
+        self.try_p2p_connect_star(hub)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_p2p_connect_star(&self, hub: &str) -> Result<(), Error> {
+        let edges = self
+            .sorted_node_names()
+            .into_iter()
+            .filter(|node_name| *node_name != hub)
+            .map(|node_name| (hub, node_name))
+            .collect::<Vec<_>>();
+        self.try_p2p_connect_edges(&edges)
+    }
+
+    /// Connect the nodes into a random `degree`-regular graph, i.e. every node has exactly
+    /// `degree` peers. The same `seed` generates the same graph.
+    pub fn p2p_connect_random_regular(&self, degree: usize, seed: u64) {
+        self.try_p2p_connect_random_regular(degree, seed)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_p2p_connect_random_regular(&self, degree: usize, seed: u64) -> Result<(), Error> {
+        let node_names = self.sorted_node_names();
+        let edges = random_regular_edges(node_names.len(), degree, seed)?
+            .into_iter()
+            .map(|(a, b)| (node_names[a], node_names[b]))
+            .collect::<Vec<_>>();
+        self.try_p2p_connect_edges(&edges)
+    }
+
+    /// Connect the node pairs of `edges`
+    pub fn p2p_connect_edges(&self, edges: &[(&str, &str)]) {
+        self.try_p2p_connect_edges(edges)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_p2p_connect_edges(&self, edges: &[(&str, &str)]) -> Result<(), Error> {
+        for (node_name_a, node_name_b) in edges {
+            let node_a = self.try_get_node(node_name_a)?;
+            let node_b = self.try_get_node(node_name_b)?;
+            try_connect_pair(node_a, node_b)?;
+        }
+        Ok(())
+    }
+
+    /// Split the network into `groups` of node names: the connections across groups are cut
+    /// and every node bans the nodes of the other groups, so that the connections don't
+    /// re-form via discovery. Every node should belong to exactly one group.
+    ///
+    /// NOTE: ckb bans by IP, so the bans only take effect between nodes on different hosts.
+    /// The nodes sharing an IP, e.g. the local nodes on the loopback address, are only
+    /// disconnected, and they must be started with
+    /// `AppConfigBuilder::discovery_local_address(false)`, otherwise discovery re-connects them
+    /// and an error is returned before cutting anything.
+    pub fn partition(&mut self, groups: &[&[&str]]) {
+        self.try_partition(groups)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_partition(&mut self, groups: &[&[&str]]) -> Result<(), Error> {
+        let cross_pairs = self.try_check_partition(groups)?;
+        let mut cut_edges = Vec::new();
+        for (node_name_a, node_name_b) in cross_pairs {
+            let node_a = self.try_get_node(&node_name_a)?;
+            let node_b = self.try_get_node(&node_name_b)?;
+            try_ban(node_a, node_b)?;
+            if node_a.try_is_p2p_connected(node_b)? {
+                node_a.try_p2p_disconnect(node_b)?;
+                cut_edges.push((node_name_a, node_name_b));
+            }
+        }
+        crate::info!(
+            "[Nodes] PARTITION groups: {:?}, cut: {:?}",
+            groups,
+            cut_edges
+        );
+        self.cut_edges.extend(cut_edges);
+        Ok(())
+    }
+
+    /// Delete the bans inserted by `partition` and restore the connections it cut
+    pub fn heal(&mut self) {
+        self.try_heal().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_heal(&mut self) -> Result<(), Error> {
+        for node in self.nodes() {
+            for banned in node.rpc_client().try_get_banned_addresses()? {
+                if banned.ban_reason == PARTITION_BAN_REASON {
+                    node.rpc_client().try_set_ban(
+                        banned.address,
+                        "delete".to_string(),
+                        None,
+                        None,
+                        None,
+                    )?;
+                }
+            }
+        }
+        let cut_edges = self.cut_edges.drain(..).collect::<Vec<_>>();
+        crate::info!("[Nodes] HEAL reconnect: {:?}", cut_edges);
+        for (node_name_a, node_name_b) in cut_edges.iter() {
+            try_connect_pair(
+                self.try_get_node(node_name_a)?,
+                self.try_get_node(node_name_b)?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Check that `groups` partition the nodes, i.e. every group member exists and every node
+    /// belongs to exactly one group, and that the nodes of different groups are separable.
+    /// Return the node pairs across the groups.
+    pub(super) fn try_check_partition(
+        &self,
+        groups: &[&[&str]],
+    ) -> Result<Vec<(String, String)>, Error> {
+        let mut grouped = HashSet::new();
+        for node_name in groups.iter().flat_map(|group| group.iter()) {
+            self.try_get_node(node_name)?;
+            if !grouped.insert(*node_name) {
+                return Err(Error::Other(format!(
+                    "node \"{}\" is in more than one group",
+                    node_name
+                )));
+            }
+        }
+
+        let mut group_of = Vec::new();
+        for node_name in self.sorted_node_names() {
+            let index = groups
+                .iter()
+                .position(|group| group.contains(&node_name))
+                .ok_or_else(|| {
+                    Error::Other(format!("node \"{}\" is not in any group", node_name))
+                })?;
+            group_of.push((node_name.to_string(), index));
+        }
+
+        let mut cross_pairs = Vec::new();
+        for (node_name_a, group_a) in group_of.iter() {
+            for (node_name_b, group_b) in group_of.iter() {
+                if group_a != group_b {
+                    let node_a = self.try_get_node(node_name_a)?;
+                    let node_b = self.try_get_node(node_name_b)?;
+                    try_ensure_separable(node_a, node_b)?;
+                    cross_pairs.push((node_name_a.clone(), node_name_b.clone()));
+                }
+            }
+        }
+        Ok(cross_pairs)
+    }
+
+    pub(super) fn try_get_node(&self, node_name: &str) -> Result<&Node, Error> {
+        self.as_ref()
+            .get(node_name)
+            .ok_or_else(|| Error::NotFound(format!("node \"{}\"", node_name)))
+    }
+
+    fn sorted_node_names(&self) -> Vec<&str> {
+        let mut node_names = self.node_names().map(String::as_str).collect::<Vec<_>>();
+        node_names.sort_unstable();
+        node_names
+    }
+}
+
+/// Return an error if `node` and `other` share an IP, so that they can't ban each other, while
+/// `node` still dials the local addresses learned via discovery
+fn try_ensure_separable(node: &Node, other: &Node) -> Result<(), Error> {
+    let ip = p2p_ip(&node.p2p_address());
+    if ip.is_none() || ip != p2p_ip(&other.p2p_address()) || !discovers_local_address(node) {
+        return Ok(());
+    }
+    Err(Error::Other(format!(
+        "node \"{}\" and node \"{}\" share IP {}, which can not be banned, start them with \
+         `AppConfigBuilder::discovery_local_address(false)` to partition them",
+        node.node_name(),
+        other.node_name(),
+        ip.unwrap_or_default()
+    )))
+}
+
+/// Whether `discovery_local_address` is enabled in the ckb.toml of `node`. The remote nodes are
+/// assumed to be enabled.
+fn discovers_local_address(node: &Node) -> bool {
+    let config = node
+        .working_dir()
+        .and_then(|working_dir| fs::read_to_string(working_dir.join("ckb.toml")).ok())
+        .unwrap_or_default();
+    !config.lines().any(|line| {
+        let line = line.split('#').next().unwrap_or_default();
+        line.split_whitespace().collect::<String>() == "discovery_local_address=false"
+    })
+}
+
+/// Let `node` ban the IP of `other`, unless they share the same IP
+fn try_ban(node: &Node, other: &Node) -> Result<(), Error> {
+    let other_ip = match p2p_ip(&other.p2p_address()) {
+        Some(ip) => ip,
+        None => return Ok(()),
+    };
+    if p2p_ip(&node.p2p_address()).as_ref() == Some(&other_ip) {
+        return Ok(());
+    }
+    node.rpc_client().try_set_ban(
+        other_ip,
+        "insert".to_string(),
+        Some(Timestamp::from(PARTITION_BAN_TIME)),
+        Some(false),
+        Some(PARTITION_BAN_REASON.to_string()),
+    )
+}
+
+/// Generate a random `degree`-regular graph over `n` nodes via the pairing model, retrying
+/// when the pairing contains a self-loop or a duplicated edge.
+fn random_regular_edges(n: usize, degree: usize, seed: u64) -> Result<Vec<(usize, usize)>, Error> {
+    if degree >= n || (n * degree) % 2 != 0 {
+        return Err(Error::Other(format!(
+            "no {}-regular graph over {} nodes",
+            degree, n
+        )));
+    }
+    // xorshift64*, enough for picking the topology
+    let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    let mut next_random = move || {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    };
+    'retry: for _ in 0..1000 {
+        let mut points = (0..n)
+            .flat_map(|node| ::std::iter::repeat(node).take(degree))
+            .collect::<Vec<_>>();
+        for i in (1..points.len()).rev() {
+            let j = (next_random() % (i as u64 + 1)) as usize;
+            points.swap(i, j);
+        }
+        let mut edges = HashSet::new();
+        for pair in points.chunks(2) {
+            let (a, b) = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+            if a == b || !edges.insert((a, b)) {
+                continue 'retry;
+            }
+        }
+        let mut edges = edges.into_iter().collect::<Vec<_>>();
+        edges.sort_unstable();
+        return Ok(edges);
+    }
+    Err(Error::Other(format!(
+        "failed to generate a {}-regular graph over {} nodes",
+        degree, n
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::random_regular_edges;
+    use std::collections::HashSet;
+
+    fn assert_regular(n: usize, degree: usize, edges: &[(usize, usize)]) {
+        assert_eq!(edges.len(), n * degree / 2);
+        let mut degrees = vec![0; n];
+        for &(a, b) in edges {
+            assert!(a < b && b < n, "invalid edge ({}, {})", a, b);
+            degrees[a] += 1;
+            degrees[b] += 1;
+        }
+        assert!(degrees.iter().all(|&d| d == degree), "{:?}", degrees);
+        let unique = edges.iter().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), edges.len());
+    }
+
+    #[test]
+    fn test_random_regular_edges() {
+        for &(n, degree) in &[(4, 2), (5, 2), (6, 3), (10, 4), (20, 3)] {
+            for seed in 0..10 {
+                let edges = random_regular_edges(n, degree, seed).unwrap();
+                assert_regular(n, degree, &edges);
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_regular_edges_same_seed() {
+        assert_eq!(
+            random_regular_edges(10, 3, 42).unwrap(),
+            random_regular_edges(10, 3, 42).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_random_regular_edges_impossible() {
+        // degree must be less than n
+        assert!(random_regular_edges(3, 3, 0).is_err());
+        // n * degree must be even
+        assert!(random_regular_edges(5, 3, 0).is_err());
+    }
+}
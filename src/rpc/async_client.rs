@@ -0,0 +1,266 @@
+use super::compat;
+use super::v2021::AsyncInner2021;
+use super::CkbVersion;
+use crate::Error;
+use ckb_jsonrpc_types::{
+    BannedAddr, Block, BlockNumber, BlockTemplate, BlockView, CellWithStatus, Consensus, EpochView,
+    EstimateCycles, HeaderView, LocalNode, OutPoint, RawTxPool, RemoteNode, Timestamp, Transaction,
+    TransactionWithStatusResponse, TxPoolInfo,
+};
+use ckb_types::core::{BlockNumber as CoreBlockNumber, Version as CoreVersion};
+use ckb_types::{packed::Byte32, prelude::*, H256};
+use jsonrpc_core::serde::{de::DeserializeOwned, Serialize};
+use std::time::{Duration, Instant};
+
+/// The async counterpart of [`RpcClient`](super::RpcClient), for scenarios that already run
+/// inside a tokio runtime, e.g. mixing `Connector` p2p messages with RPC calls.
+///
+/// Unlike `RpcClient`, every method is fallible and returns `Result<_, Error>`.
+pub struct AsyncRpcClient {
+    version: CkbVersion,
+    inner2021: AsyncInner2021,
+}
+
+impl Clone for AsyncRpcClient {
+    fn clone(&self) -> AsyncRpcClient {
+        AsyncRpcClient::new(self.inner2021.url.as_str(), self.version)
+    }
+}
+
+impl AsyncRpcClient {
+    pub fn new(uri: &str, version: CkbVersion) -> Self {
+        Self {
+            inner2021: AsyncInner2021::new(uri),
+            version,
+        }
+    }
+
+    pub fn version(&self) -> CkbVersion {
+        self.version
+    }
+
+    pub fn is_ckb2021(&self) -> bool {
+        self.version.is_ckb2021()
+    }
+
+    /// See `RpcClient::call_compat`
+    async fn call_compat<P: Serialize, T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<T, Error> {
+        let params = compat::encode(self.version, params)?;
+        let mut results: Vec<serde_json::Value> = self
+            .inner2021
+            .batch(vec![(method.to_string(), params)])
+            .await?;
+        let result = results.pop().expect("one result per call");
+        compat::decode(self.version, result)
+    }
+
+    pub fn url(&self) -> &str {
+        self.inner2021.url.as_ref()
+    }
+
+    pub fn inner(&self) -> &AsyncInner2021 {
+        &self.inner2021
+    }
+
+    pub async fn get_block(&self, hash: Byte32) -> Result<Option<BlockView>, Error> {
+        let hash: H256 = hash.unpack();
+        if self.version.is_ckb2021() {
+            self.inner2021.get_block(hash).await
+        } else {
+            self.call_compat("get_block", (hash,)).await
+        }
+    }
+
+    pub async fn get_block_by_number(
+        &self,
+        number: CoreBlockNumber,
+    ) -> Result<Option<BlockView>, Error> {
+        let number: BlockNumber = number.into();
+        if self.version.is_ckb2021() {
+            self.inner2021.get_block_by_number(number).await
+        } else {
+            self.call_compat("get_block_by_number", (number,)).await
+        }
+    }
+
+    pub async fn get_header(&self, hash: Byte32) -> Result<Option<HeaderView>, Error> {
+        let hash: H256 = hash.unpack();
+        if self.version.is_ckb2021() {
+            self.inner2021.get_header(hash).await
+        } else {
+            self.call_compat("get_header", (hash,)).await
+        }
+    }
+
+    pub async fn get_header_by_number(
+        &self,
+        number: CoreBlockNumber,
+    ) -> Result<Option<HeaderView>, Error> {
+        let number: BlockNumber = number.into();
+        if self.version.is_ckb2021() {
+            self.inner2021.get_header_by_number(number).await
+        } else {
+            self.call_compat("get_header_by_number", (number,)).await
+        }
+    }
+
+    pub async fn get_transaction(
+        &self,
+        hash: Byte32,
+    ) -> Result<Option<TransactionWithStatusResponse>, Error> {
+        self.inner2021
+            .get_transaction(hash.unpack())
+            .await
+            .map(compat::normalize_transaction)
+    }
+
+    pub async fn get_block_hash(&self, number: CoreBlockNumber) -> Result<Option<Byte32>, Error> {
+        self.inner2021
+            .get_block_hash(number.into())
+            .await
+            .map(|x| x.map(|h256| h256.pack()))
+    }
+
+    pub async fn get_tip_header(&self) -> Result<HeaderView, Error> {
+        if self.version.is_ckb2021() {
+            self.inner2021.get_tip_header().await
+        } else {
+            self.call_compat("get_tip_header", ()).await
+        }
+    }
+
+    pub async fn get_tip_block_number(&self) -> Result<CoreBlockNumber, Error> {
+        self.inner2021.get_tip_block_number().await.map(Into::into)
+    }
+
+    pub async fn get_live_cell(
+        &self,
+        out_point: OutPoint,
+        with_data: bool,
+    ) -> Result<CellWithStatus, Error> {
+        self.inner2021.get_live_cell(out_point, with_data).await
+    }
+
+    pub async fn get_current_epoch(&self) -> Result<EpochView, Error> {
+        self.inner2021.get_current_epoch().await
+    }
+
+    pub async fn get_consensus(&self) -> Result<Consensus, Error> {
+        if self.version.is_ckb2021() {
+            self.inner2021.get_consensus().await
+        } else {
+            self.call_compat("get_consensus", ()).await
+        }
+    }
+
+    pub async fn estimate_cycles(&self, tx: Transaction) -> Result<EstimateCycles, Error> {
+        if self.version.has_estimate_cycles() {
+            self.inner2021.estimate_cycles(tx).await
+        } else {
+            self.call_compat("dry_run_transaction", (tx,)).await
+        }
+    }
+
+    pub async fn local_node_info(&self) -> Result<LocalNode, Error> {
+        self.inner2021.local_node_info().await
+    }
+
+    pub async fn get_peers(&self) -> Result<Vec<RemoteNode>, Error> {
+        self.inner2021.get_peers().await
+    }
+
+    pub async fn get_banned_addresses(&self) -> Result<Vec<BannedAddr>, Error> {
+        self.inner2021.get_banned_addresses().await
+    }
+
+    pub async fn set_ban(
+        &self,
+        address: String,
+        command: String,
+        ban_time: Option<Timestamp>,
+        absolute: Option<bool>,
+        reason: Option<String>,
+    ) -> Result<(), Error> {
+        self.inner2021
+            .set_ban(address, command, ban_time, absolute, reason)
+            .await
+    }
+
+    pub async fn get_block_template(
+        &self,
+        bytes_limit: Option<u64>,
+        proposals_limit: Option<u64>,
+        max_version: Option<CoreVersion>,
+    ) -> Result<BlockTemplate, Error> {
+        let bytes_limit = bytes_limit.map(Into::into);
+        let proposals_limit = proposals_limit.map(Into::into);
+        let max_version = max_version.map(Into::into);
+        if self.version.is_ckb2021() {
+            self.inner2021
+                .get_block_template(bytes_limit, proposals_limit, max_version)
+                .await
+        } else {
+            self.call_compat(
+                "get_block_template",
+                (bytes_limit, proposals_limit, max_version),
+            )
+            .await
+        }
+    }
+
+    pub async fn submit_block(&self, work_id: String, block: Block) -> Result<Byte32, Error> {
+        let hash: H256 = if self.version.is_ckb2021() {
+            self.inner2021.submit_block(work_id, block).await?
+        } else {
+            self.call_compat("submit_block", (work_id, block)).await?
+        };
+        Ok(hash.pack())
+    }
+
+    pub async fn get_block_median_time(
+        &self,
+        block_hash: Byte32,
+    ) -> Result<Option<Timestamp>, Error> {
+        self.inner2021
+            .get_block_median_time(block_hash.unpack())
+            .await
+    }
+
+    pub async fn send_transaction(&self, tx: Transaction) -> Result<Byte32, Error> {
+        let hash = self
+            .inner2021
+            .send_transaction(tx, Some("passthrough".to_string()))
+            .await?;
+        // See the NOTE inside `RpcClient::try_send_transaction`
+        let start_time = Instant::now();
+        while start_time.elapsed() <= Duration::from_secs(20) {
+            if let Some(txstatus) = self.inner2021.get_transaction(hash.clone()).await? {
+                if txstatus.tx_status.status != ckb_jsonrpc_types::Status::Unknown {
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        Ok(hash.pack())
+    }
+
+    pub async fn tx_pool_info(&self) -> Result<TxPoolInfo, Error> {
+        self.inner2021.tx_pool_info().await
+    }
+
+    pub async fn add_node(&self, peer_id: String, address: String) -> Result<(), Error> {
+        self.inner2021.add_node(peer_id, address).await
+    }
+
+    pub async fn remove_node(&self, peer_id: String) -> Result<(), Error> {
+        self.inner2021.remove_node(peer_id).await
+    }
+
+    pub async fn get_raw_tx_pool(&self, verbose: Option<bool>) -> Result<RawTxPool, Error> {
+        self.inner2021.get_raw_tx_pool(verbose).await
+    }
+}
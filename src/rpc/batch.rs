@@ -0,0 +1,127 @@
+use crate::Error;
+use jsonrpc_core::response::{Output, Response};
+use jsonrpc_core::serde::de::DeserializeOwned;
+use jsonrpc_core::Id;
+use std::collections::HashMap;
+
+/// The maximal number of calls sent within one batch request by the range helpers
+pub(crate) const BATCH_CHUNK_SIZE: usize = 200;
+
+/// Build the JSON body of a batch request. Return the body and the ids of the calls, in order.
+pub(crate) fn build_batch_request(
+    calls: Vec<(String, serde_json::Value)>,
+    mut next_id: impl FnMut() -> u64,
+) -> (Vec<serde_json::Value>, Vec<u64>) {
+    let mut ids = Vec::with_capacity(calls.len());
+    let requests = calls
+        .into_iter()
+        .map(|(method, params)| {
+            let id = next_id();
+            ids.push(id);
+            serde_json::json!({
+                "id": id,
+                "jsonrpc": "2.0",
+                "method": method,
+                "params": params,
+            })
+        })
+        .collect();
+    (requests, ids)
+}
+
+/// Match the outputs of a batch response with the ids of the calls. The server may reply in
+/// any order, so the outputs are re-ordered to the order of `ids`.
+pub(crate) fn parse_batch_response<T: DeserializeOwned>(
+    ids: Vec<u64>,
+    response: Response,
+) -> Result<Vec<T>, Error> {
+    let outputs = match response {
+        Response::Batch(outputs) => outputs,
+        // The server replies a single failure when it cannot parse the whole batch
+        Response::Single(Output::Failure(failure)) if failure.id == Id::Null => {
+            return Err(crate::rpc::error::Error {
+                inner: failure.error,
+            }
+            .into());
+        }
+        Response::Single(output) => vec![output],
+    };
+    let mut outputs = outputs
+        .into_iter()
+        .map(|output| (output.id().clone(), output))
+        .collect::<HashMap<_, _>>();
+    ids.into_iter()
+        .map(|id| {
+            let output = outputs.remove(&Id::Num(id)).ok_or_else(|| {
+                Error::Other(format!("missing response of batch request id {}", id))
+            })?;
+            match output {
+                Output::Success(success) => {
+                    serde_json::from_value(success.result).map_err(Into::into)
+                }
+                Output::Failure(failure) => Err(crate::rpc::error::Error {
+                    inner: failure.error,
+                }
+                .into()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_batch_response;
+    use crate::Error;
+    use jsonrpc_core::response::Response;
+
+    fn response(json: &str) -> Response {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_parse_batch_response_out_of_order() {
+        let response = response(
+            r#"[
+                {"jsonrpc": "2.0", "id": 3, "result": "0x3"},
+                {"jsonrpc": "2.0", "id": 1, "result": "0x1"},
+                {"jsonrpc": "2.0", "id": 2, "result": "0x2"}
+            ]"#,
+        );
+        let results: Vec<String> = parse_batch_response(vec![1, 2, 3], response).unwrap();
+        assert_eq!(results, vec!["0x1", "0x2", "0x3"]);
+    }
+
+    #[test]
+    fn test_parse_batch_response_error_entry() {
+        let response = response(
+            r#"[
+                {"jsonrpc": "2.0", "id": 1, "result": "0x1"},
+                {"jsonrpc": "2.0", "id": 2, "error": {"code": -32601, "message": "Method not found"}}
+            ]"#,
+        );
+        let result = parse_batch_response::<String>(vec![1, 2], response);
+        match result {
+            Err(Error::Rpc(err)) => assert_eq!(err.code(), -32601),
+            other => panic!("expect rpc error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_batch_response_missing_entry() {
+        let response = response(r#"[{"jsonrpc": "2.0", "id": 1, "result": "0x1"}]"#);
+        let result = parse_batch_response::<String>(vec![1, 2], response);
+        assert!(matches!(result, Err(Error::Other(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn test_parse_batch_response_whole_batch_failure() {
+        let response = response(
+            r#"{"jsonrpc": "2.0", "id": null, "error": {"code": -32700, "message": "Parse error"}}"#,
+        );
+        let result = parse_batch_response::<String>(vec![1, 2], response);
+        match result {
+            Err(Error::Rpc(err)) => assert_eq!(err.code(), -32700),
+            other => panic!("expect rpc error, got {:?}", other),
+        }
+    }
+}
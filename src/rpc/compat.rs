@@ -0,0 +1,57 @@
+//! Shims between the RPC types of the current ckb-jsonrpc-types and the ones of older ckb
+//! releases.
+
+use super::version::CkbVersion;
+use crate::Error;
+use ckb_jsonrpc_types::{Status, TransactionWithStatusResponse};
+use jsonrpc_core::serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// Convert a pre-ckb2021 JSON item into the current shape
+pub(crate) fn item2019_to_item2021(item2019: Value) -> Result<Value, Error> {
+    let raw2019 = serde_json::to_string(&item2019)?;
+    let raw2021 = raw2019
+        // interfaces that includes block header
+        .replace("uncles_hash", "extra_hash")
+        // get_consensus
+        .replace(
+            "\"permanent_difficulty_in_dummy\":",
+            "\"hardfork_features\":[],\"permanent_difficulty_in_dummy\":",
+        );
+    serde_json::from_str(&raw2021).map_err(Into::into)
+}
+
+/// Convert a JSON item of the current shape into the pre-ckb2021 one
+pub(crate) fn item2021_to_item2019(item2021: Value) -> Result<Value, Error> {
+    let raw2021 = serde_json::to_string(&item2021)?;
+    let raw2019 = raw2021.replace("extra_hash", "uncles_hash");
+    serde_json::from_str(&raw2019).map_err(Into::into)
+}
+
+/// Deserialize the result replied by a node of `version`
+pub(crate) fn decode<T: DeserializeOwned>(version: CkbVersion, result: Value) -> Result<T, Error> {
+    let result = if version.is_ckb2021() {
+        result
+    } else {
+        item2019_to_item2021(result)?
+    };
+    serde_json::from_value(result).map_err(Into::into)
+}
+
+/// Serialize the params sent to a node of `version`
+pub(crate) fn encode<P: Serialize>(version: CkbVersion, params: P) -> Result<Value, Error> {
+    let params = serde_json::to_value(params)?;
+    if version.is_ckb2021() {
+        Ok(params)
+    } else {
+        item2021_to_item2019(params)
+    }
+}
+
+/// Since v0.105, `get_transaction` replies a response with status "unknown" rather than `null`
+/// for unknown transactions. Normalize both into `None`.
+pub(crate) fn normalize_transaction(
+    transaction: Option<TransactionWithStatusResponse>,
+) -> Option<TransactionWithStatusResponse> {
+    transaction.filter(|transaction| transaction.tx_status.status != Status::Unknown)
+}
@@ -1,4 +1,4 @@
-use jsonrpc_core::error::Error as JsonRpcError;
+use jsonrpc_core::error::{Error as JsonRpcError, ErrorCode};
 use std::fmt;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -17,3 +17,24 @@ impl fmt::Display for Error {
 }
 
 impl ::std::error::Error for Error {}
+
+impl Error {
+    /// The JSON-RPC error object returned by the node
+    pub fn inner(&self) -> &JsonRpcError {
+        &self.inner
+    }
+
+    pub fn code(&self) -> i64 {
+        self.inner.code.code()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.inner.message
+    }
+
+    /// Whether the node does not serve the method, e.g. its RPC module is not enabled or the
+    /// node is too old
+    pub fn is_method_not_found(&self) -> bool {
+        self.inner.code == ErrorCode::MethodNotFound
+    }
+}
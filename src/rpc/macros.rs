@@ -1,5 +1,29 @@
 #[macro_export]
 macro_rules! jsonrpc {
+    (
+        $(#[$struct_attr:meta])*
+        pub struct $struct_name:ident, pub async struct $async_struct_name:ident {$(
+            $(#[$attr:meta])*
+            pub fn $method:ident(&$selff:ident $(, $arg_name:ident: $arg_ty:ty)*)
+                -> $return_ty:ty;
+        )*}
+    ) => (
+        jsonrpc!(
+            $(#[$struct_attr])*
+            pub struct $struct_name {$(
+                $(#[$attr])*
+                pub fn $method(&$selff $(, $arg_name: $arg_ty)*) -> $return_ty;
+            )*}
+        );
+
+        async_jsonrpc!(
+            $(#[$struct_attr])*
+            pub struct $async_struct_name {$(
+                $(#[$attr])*
+                pub fn $method(&$selff $(, $arg_name: $arg_ty)*) -> $return_ty;
+            )*}
+        );
+    );
     (
         $(#[$struct_attr:meta])*
         pub struct $struct_name:ident {$(
@@ -22,10 +46,27 @@ macro_rules! jsonrpc {
                 $struct_name { url, id_generator, client: &$crate::rpc::HTTP_CLIENT, }
             }
 
+            /// Send `calls`, a list of `(method, params)`, within one JSON-RPC batch request and
+            /// return the results in the order of `calls`.
+            #[allow(dead_code)]
+            pub fn batch<T>(&self, calls: Vec<(String, serde_json::Value)>) -> Result<Vec<T>, $crate::Error>
+            where
+                T: jsonrpc_core::serde::de::DeserializeOwned,
+            {
+                if calls.is_empty() {
+                    return Ok(Vec::new());
+                }
+                let (requests, ids) =
+                    $crate::rpc::batch::build_batch_request(calls, || self.id_generator.next());
+                let resp = self.client.post(self.url.clone()).json(&requests).send()?;
+                let response = resp.json::<jsonrpc_core::response::Response>()?;
+                $crate::rpc::batch::parse_batch_response(ids, response)
+            }
+
             $(
                 #[allow(dead_code)]
                 $(#[$attr])*
-                pub fn $method(&$selff $(, $arg_name: $arg_ty)*) -> Result<$return_ty, ckb_error::AnyError> {
+                pub fn $method(&$selff $(, $arg_name: $arg_ty)*) -> Result<$return_ty, $crate::Error> {
                     let method =
                         String::from(stringify!($method))
                             .replace("2019", "")
@@ -56,6 +97,88 @@ macro_rules! jsonrpc {
     )
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! async_jsonrpc {
+    (
+        $(#[$struct_attr:meta])*
+        pub struct $struct_name:ident {$(
+            $(#[$attr:meta])*
+            pub fn $method:ident(&$selff:ident $(, $arg_name:ident: $arg_ty:ty)*)
+                -> $return_ty:ty;
+        )*}
+    ) => (
+        $(#[$struct_attr])*
+        pub struct $struct_name {
+            // Unlike the blocking client, the async client is not shared globally: its pooled
+            // connections are bound to the tokio runtime which created them.
+            pub client: reqwest::Client,
+            pub url: reqwest::Url,
+            pub id_generator: $crate::rpc::id_generator::IdGenerator,
+        }
+
+        impl $struct_name {
+            pub fn new(uri: &str) -> Self {
+                let url = reqwest::Url::parse(uri).expect("ckb uri, e.g. \"http://127.0.0.1:8114\"");
+                let id_generator = $crate::rpc::id_generator::IdGenerator::new();
+                let client = reqwest::Client::builder()
+                    .timeout(::std::time::Duration::from_secs(30))
+                    .build()
+                    .expect("reqwest Client build");
+                $struct_name { url, id_generator, client, }
+            }
+
+            /// Send `calls`, a list of `(method, params)`, within one JSON-RPC batch request and
+            /// return the results in the order of `calls`.
+            #[allow(dead_code)]
+            pub async fn batch<T>(&self, calls: Vec<(String, serde_json::Value)>) -> Result<Vec<T>, $crate::Error>
+            where
+                T: jsonrpc_core::serde::de::DeserializeOwned,
+            {
+                if calls.is_empty() {
+                    return Ok(Vec::new());
+                }
+                let (requests, ids) =
+                    $crate::rpc::batch::build_batch_request(calls, || self.id_generator.next());
+                let resp = self.client.post(self.url.clone()).json(&requests).send().await?;
+                let response = resp.json::<jsonrpc_core::response::Response>().await?;
+                $crate::rpc::batch::parse_batch_response(ids, response)
+            }
+
+            $(
+                #[allow(dead_code)]
+                $(#[$attr])*
+                pub async fn $method(&$selff $(, $arg_name: $arg_ty)*) -> Result<$return_ty, $crate::Error> {
+                    let method =
+                        String::from(stringify!($method))
+                            .replace("2019", "")
+                            .replace("2021", "");
+
+                    let params = serialize_parameters!($($arg_name,)*);
+                    let id = $selff.id_generator.next();
+
+                    let mut req_json = serde_json::Map::new();
+                    req_json.insert("id".to_owned(), serde_json::json!(id));
+                    req_json.insert("jsonrpc".to_owned(), serde_json::json!("2.0"));
+                    req_json.insert("method".to_owned(), serde_json::json!(method));
+                    req_json.insert("params".to_owned(), params);
+
+                    let resp = $selff.client.post($selff.url.clone()).json(&req_json).send().await?;
+                    let output = resp.json::<jsonrpc_core::response::Output>().await?;
+                    match output {
+                        jsonrpc_core::response::Output::Success(success) => {
+                            serde_json::from_value(success.result).map_err(Into::into)
+                        },
+                        jsonrpc_core::response::Output::Failure(failure) => {
+                            Err($crate::rpc::error::Error{ inner: failure.error }.into())
+                        }
+                    }
+                }
+            )*
+        }
+    )
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! serialize_parameters {
@@ -1,18 +1,32 @@
+mod async_client;
+pub(crate) mod batch;
+mod compat;
 mod id_generator;
 #[macro_use]
 mod macros;
-mod error;
+pub(crate) mod error;
 mod v2021;
+mod version;
 
+pub use async_client::AsyncRpcClient;
+pub use error::Error as RpcError;
+pub use version::CkbVersion;
+
+use crate::Error;
+use batch::BATCH_CHUNK_SIZE;
 use ckb_error::AnyError;
 // TODO replace json types with core types
-use ckb_jsonrpc_types::{Alert, BannedAddr, Block, BlockTemplate, BlockView, CellWithStatus, ChainInfo, Consensus, Cycle, EpochView, EstimateCycles, HeaderView, LocalNode, OutPoint, RawTxPool, RemoteNode, Timestamp, Transaction, TransactionWithStatusResponse, TxPoolInfo};
-use ckb_types::core::{BlockNumber as CoreBlockNumber, Capacity as CoreCapacity, EpochNumber as CoreEpochNumber, TransactionWithStatus, Version as CoreVersion};
-use ckb_types::{packed::Byte32, prelude::*};
+use ckb_jsonrpc_types::{Alert, BannedAddr, Block, BlockNumber, BlockTemplate, BlockView, CellWithStatus, ChainInfo, Consensus, Cycle, EpochView, EstimateCycles, HeaderView, IndexerCell, IndexerCellsCapacity, IndexerOrder, IndexerPagination, IndexerSearchKey, IndexerTip, IndexerTx, JsonBytes, LocalNode, OutPoint, RawTxPool, RemoteNode, Timestamp, Transaction, TransactionWithStatusResponse, TxPoolInfo};
+use ckb_types::core::{BlockNumber as CoreBlockNumber, Capacity as CoreCapacity, EpochNumber as CoreEpochNumber, EpochNumberWithFraction as CoreEpochNumberWithFraction, TransactionWithStatus, Version as CoreVersion};
+use ckb_types::{packed::Byte32, prelude::*, H256};
+use jsonrpc_core::serde::{de::DeserializeOwned, Serialize};
 use lazy_static::lazy_static;
 use std::time::{Duration, Instant};
 use v2021::Inner2021;
 
+/// The page size used when walking through all pages of the indexer RPCs
+const INDEXER_PAGE_SIZE: u32 = 1000;
+
 lazy_static! {
     pub static ref HTTP_CLIENT: reqwest::blocking::Client = reqwest::blocking::Client::builder()
         .timeout(::std::time::Duration::from_secs(30))
@@ -20,48 +34,49 @@ lazy_static! {
         .expect("reqwest Client build");
 }
 
-macro_rules! item2019_to_item2021 {
-    ($item2019:expr) => {{
-        let raw2019 = serde_json::to_string(&$item2019).unwrap();
-        let raw2021 = raw2019
-            // interfaces that includes block header
-            .replace("uncles_hash", "extra_hash")
-            // get_consensus
-            .replace(
-                "\"permanent_difficulty_in_dummy\":",
-                "\"hardfork_features\":[],\"permanent_difficulty_in_dummy\":",
-            );
-        serde_json::from_str(&raw2021).unwrap()
-    }};
-}
-
-macro_rules! item2021_to_item2019 {
-    ($item2021:expr) => {{
-        let raw2021 = serde_json::to_string(&$item2021).unwrap();
-        let raw2019 = raw2021.replace("extra_hash", "uncles_hash");
-        serde_json::from_str(&raw2019).unwrap()
-    }};
-}
-
 pub struct RpcClient {
-    pub ckb2021: bool,
+    version: CkbVersion,
     inner2021: Inner2021,
 }
 
 impl Clone for RpcClient {
     fn clone(&self) -> RpcClient {
-        RpcClient::new(self.inner2021.url.as_str(), self.ckb2021)
+        RpcClient::new(self.inner2021.url.as_str(), self.version)
     }
 }
 
 impl RpcClient {
-    pub fn new(uri: &str, ckb2021: bool) -> Self {
+    /// Create a client of the node of `version`. The RPCs which differ among releases are
+    /// converted into the shape of the current ckb-jsonrpc-types.
+    pub fn new(uri: &str, version: CkbVersion) -> Self {
         Self {
             inner2021: Inner2021::new(uri),
-            ckb2021,
+            version,
         }
     }
 
+    pub fn version(&self) -> CkbVersion {
+        self.version
+    }
+
+    pub fn is_ckb2021(&self) -> bool {
+        self.version.is_ckb2021()
+    }
+
+    /// Call `method` with the shims of `self.version`, for the RPCs whose request or response
+    /// shape differs among releases.
+    fn call_compat<P: Serialize, T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<T, Error> {
+        let params = compat::encode(self.version, params)?;
+        let mut results: Vec<serde_json::Value> =
+            self.inner2021.batch(vec![(method.to_string(), params)])?;
+        let result = results.pop().expect("one result per call");
+        compat::decode(self.version, result)
+    }
+
     pub fn url(&self) -> &str {
         self.inner2021.url.as_ref()
     }
@@ -71,106 +86,314 @@ impl RpcClient {
     }
 
     pub fn get_block(&self, hash: Byte32) -> Option<BlockView> {
-        self.inner2021
-            .get_block(hash.unpack())
-            .expect("rpc call get_block")
+        self.try_get_block(hash).expect("rpc call get_block")
+    }
+
+    pub fn try_get_block(&self, hash: Byte32) -> Result<Option<BlockView>, Error> {
+        let hash: H256 = hash.unpack();
+        if self.version.is_ckb2021() {
+            self.inner2021.get_block(hash)
+        } else {
+            self.call_compat("get_block", (hash,))
+        }
     }
 
     pub fn get_fork_block(&self, hash: Byte32) -> Option<BlockView> {
-        self.inner2021
-            .get_fork_block(hash.unpack())
+        self.try_get_fork_block(hash)
             .expect("rpc call get_fork_block")
     }
 
+    pub fn try_get_fork_block(&self, hash: Byte32) -> Result<Option<BlockView>, Error> {
+        let hash: H256 = hash.unpack();
+        if self.version.is_ckb2021() {
+            self.inner2021.get_fork_block(hash)
+        } else {
+            self.call_compat("get_fork_block", (hash,))
+        }
+    }
+
     pub fn get_block_by_number(&self, number: CoreBlockNumber) -> Option<BlockView> {
-        self.inner2021
-            .get_block_by_number(number.into())
+        self.try_get_block_by_number(number)
             .expect("rpc call get_block_by_number")
     }
 
+    pub fn try_get_block_by_number(
+        &self,
+        number: CoreBlockNumber,
+    ) -> Result<Option<BlockView>, Error> {
+        let number: BlockNumber = number.into();
+        if self.version.is_ckb2021() {
+            self.inner2021.get_block_by_number(number)
+        } else {
+            self.call_compat("get_block_by_number", (number,))
+        }
+    }
+
     pub fn get_header(&self, hash: Byte32) -> Option<HeaderView> {
-        self.inner2021
-            .get_header(hash.unpack())
-            .expect("rpc call get_header")
+        self.try_get_header(hash).expect("rpc call get_header")
+    }
 
+    pub fn try_get_header(&self, hash: Byte32) -> Result<Option<HeaderView>, Error> {
+        let hash: H256 = hash.unpack();
+        if self.version.is_ckb2021() {
+            self.inner2021.get_header(hash)
+        } else {
+            self.call_compat("get_header", (hash,))
+        }
     }
 
     pub fn get_header_by_number(&self, number: CoreBlockNumber) -> Option<HeaderView> {
-        self.inner2021
-            .get_header_by_number(number.into())
+        self.try_get_header_by_number(number)
             .expect("rpc call get_header_by_number")
     }
 
+    pub fn try_get_header_by_number(
+        &self,
+        number: CoreBlockNumber,
+    ) -> Result<Option<HeaderView>, Error> {
+        let number: BlockNumber = number.into();
+        if self.version.is_ckb2021() {
+            self.inner2021.get_header_by_number(number)
+        } else {
+            self.call_compat("get_header_by_number", (number,))
+        }
+    }
+
+    /// Return the headers of `numbers`, fetched via JSON-RPC batch requests.
+    pub fn get_headers_by_number(
+        &self,
+        numbers: impl IntoIterator<Item = CoreBlockNumber>,
+    ) -> Vec<Option<HeaderView>> {
+        self.try_get_headers_by_number(numbers)
+            .expect("rpc call get_header_by_number in batch")
+    }
+
+    pub fn try_get_headers_by_number(
+        &self,
+        numbers: impl IntoIterator<Item = CoreBlockNumber>,
+    ) -> Result<Vec<Option<HeaderView>>, Error> {
+        self.batch_by_number("get_header_by_number", numbers)
+    }
+
+    /// Return the blocks of `numbers`, fetched via JSON-RPC batch requests.
+    pub fn get_blocks_by_number(
+        &self,
+        numbers: impl IntoIterator<Item = CoreBlockNumber>,
+    ) -> Vec<Option<BlockView>> {
+        self.try_get_blocks_by_number(numbers)
+            .expect("rpc call get_block_by_number in batch")
+    }
+
+    pub fn try_get_blocks_by_number(
+        &self,
+        numbers: impl IntoIterator<Item = CoreBlockNumber>,
+    ) -> Result<Vec<Option<BlockView>>, Error> {
+        self.batch_by_number("get_block_by_number", numbers)
+    }
+
+    /// Return the block hashes of `numbers`, fetched via JSON-RPC batch requests.
+    pub fn get_block_hashes(
+        &self,
+        numbers: impl IntoIterator<Item = CoreBlockNumber>,
+    ) -> Vec<Option<Byte32>> {
+        self.try_get_block_hashes(numbers)
+            .expect("rpc call get_block_hash in batch")
+    }
+
+    pub fn try_get_block_hashes(
+        &self,
+        numbers: impl IntoIterator<Item = CoreBlockNumber>,
+    ) -> Result<Vec<Option<Byte32>>, Error> {
+        let hashes: Vec<Option<H256>> = self.batch_by_number("get_block_hash", numbers)?;
+        Ok(hashes
+            .into_iter()
+            .map(|hash| hash.map(|h256| h256.pack()))
+            .collect())
+    }
+
+    /// Return the headers of `hashes`, fetched via JSON-RPC batch requests.
+    pub fn get_headers(&self, hashes: Vec<Byte32>) -> Vec<Option<HeaderView>> {
+        self.try_get_headers(hashes)
+            .expect("rpc call get_header in batch")
+    }
+
+    pub fn try_get_headers(&self, hashes: Vec<Byte32>) -> Result<Vec<Option<HeaderView>>, Error> {
+        let mut headers = Vec::with_capacity(hashes.len());
+        for chunk in hashes.chunks(BATCH_CHUNK_SIZE) {
+            let calls = chunk
+                .iter()
+                .map(|hash| {
+                    let hash: H256 = hash.unpack();
+                    let params = serde_json::to_value((hash,))?;
+                    Ok(("get_header".to_string(), params))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let results: Vec<serde_json::Value> = self.inner2021.batch(calls)?;
+            for result in results {
+                headers.push(compat::decode(self.version, result)?);
+            }
+        }
+        Ok(headers)
+    }
+
+    fn batch_by_number<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        numbers: impl IntoIterator<Item = CoreBlockNumber>,
+    ) -> Result<Vec<T>, Error> {
+        let numbers = numbers.into_iter().collect::<Vec<_>>();
+        let mut results = Vec::with_capacity(numbers.len());
+        for chunk in numbers.chunks(BATCH_CHUNK_SIZE) {
+            let calls = chunk
+                .iter()
+                .map(|number| {
+                    let params = serde_json::to_value((BlockNumber::from(*number),))?;
+                    Ok((method.to_string(), params))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let values: Vec<serde_json::Value> = self.inner2021.batch(calls)?;
+            for value in values {
+                results.push(compat::decode(self.version, value)?);
+            }
+        }
+        Ok(results)
+    }
+
     pub fn get_transaction(&self, hash: Byte32) -> Option<TransactionWithStatusResponse> {
+        self.try_get_transaction(hash)
+            .expect("rpc call get_transaction")
+    }
+
+    /// Return `None` if the transaction is unknown, no matter the node replies `null` (before
+    /// v0.105) or a response with status "unknown".
+    pub fn try_get_transaction(
+        &self,
+        hash: Byte32,
+    ) -> Result<Option<TransactionWithStatusResponse>, Error> {
         self.inner2021
             .get_transaction(hash.unpack())
-            .expect("rpc call get_transaction")
+            .map(compat::normalize_transaction)
     }
 
     pub fn get_block_hash(&self, number: CoreBlockNumber) -> Option<Byte32> {
+        self.try_get_block_hash(number)
+            .expect("rpc call get_block_hash")
+    }
+
+    pub fn try_get_block_hash(&self, number: CoreBlockNumber) -> Result<Option<Byte32>, Error> {
         self.inner()
             .get_block_hash(number.into())
-            .expect("rpc call get_block_hash")
-            .map(|x| x.pack())
+            .map(|x| x.map(|h256| h256.pack()))
     }
 
     pub fn get_tip_header(&self) -> HeaderView {
-        self.inner2021
-            .get_tip_header()
-            .expect("rpc call get_block_hash")
+        self.try_get_tip_header().expect("rpc call get_tip_header")
+    }
+
+    pub fn try_get_tip_header(&self) -> Result<HeaderView, Error> {
+        if self.version.is_ckb2021() {
+            self.inner2021.get_tip_header()
+        } else {
+            self.call_compat("get_tip_header", ())
+        }
     }
 
     pub fn get_live_cell(&self, out_point: OutPoint, with_data: bool) -> CellWithStatus {
-        self.inner2021
-            .get_live_cell(out_point, with_data)
+        self.try_get_live_cell(out_point, with_data)
             .expect("rpc call get_live_cell")
     }
 
+    pub fn try_get_live_cell(
+        &self,
+        out_point: OutPoint,
+        with_data: bool,
+    ) -> Result<CellWithStatus, Error> {
+        self.inner2021.get_live_cell(out_point, with_data)
+    }
+
     pub fn get_tip_block_number(&self) -> CoreBlockNumber {
-        self.inner()
-            .get_tip_block_number()
+        self.try_get_tip_block_number()
             .expect("rpc call get_tip_block_number")
-            .into()
+    }
+
+    pub fn try_get_tip_block_number(&self) -> Result<CoreBlockNumber, Error> {
+        self.inner().get_tip_block_number().map(Into::into)
     }
 
     pub fn get_current_epoch(&self) -> EpochView {
-        self.inner()
-            .get_current_epoch()
+        self.try_get_current_epoch()
             .expect("rpc call get_current_epoch")
     }
 
+    pub fn try_get_current_epoch(&self) -> Result<EpochView, Error> {
+        self.inner().get_current_epoch()
+    }
+
     pub fn get_epoch_by_number(&self, number: CoreEpochNumber) -> Option<EpochView> {
-        self.inner()
-            .get_epoch_by_number(number.into())
+        self.try_get_epoch_by_number(number)
             .expect("rpc call get_epoch_by_number")
     }
 
+    pub fn try_get_epoch_by_number(
+        &self,
+        number: CoreEpochNumber,
+    ) -> Result<Option<EpochView>, Error> {
+        self.inner().get_epoch_by_number(number.into())
+    }
+
     pub fn get_consensus(&self) -> Consensus {
-        self.inner2021
-            .get_consensus()
-            .expect("rpc call get_consensus")
+        self.try_get_consensus().expect("rpc call get_consensus")
+    }
+
+    pub fn try_get_consensus(&self) -> Result<Consensus, Error> {
+        if self.version.is_ckb2021() {
+            self.inner2021.get_consensus()
+        } else {
+            self.call_compat("get_consensus", ())
+        }
     }
 
     pub fn estimate_cycles(&self, tx: Transaction) -> EstimateCycles {
-        self.inner2021.estimate_cycles(tx).expect("rpc call estimate_cycles")
+        self.try_estimate_cycles(tx)
+            .expect("rpc call estimate_cycles")
+    }
+
+    /// Estimate the cycles via RPC `estimate_cycles`, or `dry_run_transaction` before v0.105,
+    /// which replies the same shape.
+    pub fn try_estimate_cycles(&self, tx: Transaction) -> Result<EstimateCycles, Error> {
+        if self.version.has_estimate_cycles() {
+            self.inner2021.estimate_cycles(tx)
+        } else {
+            self.call_compat("dry_run_transaction", (tx,))
+        }
     }
 
     pub fn local_node_info(&self) -> LocalNode {
-        self.inner()
-            .local_node_info()
+        self.try_local_node_info()
             .expect("rpc call local_node_info")
     }
 
+    pub fn try_local_node_info(&self) -> Result<LocalNode, Error> {
+        self.inner().local_node_info()
+    }
+
     pub fn get_peers(&self) -> Vec<RemoteNode> {
-        self.inner().get_peers().expect("rpc call get_peers")
+        self.try_get_peers().expect("rpc call get_peers")
+    }
+
+    pub fn try_get_peers(&self) -> Result<Vec<RemoteNode>, Error> {
+        self.inner().get_peers()
     }
 
     pub fn get_banned_addresses(&self) -> Vec<BannedAddr> {
-        self.inner()
-            .get_banned_addresses()
+        self.try_get_banned_addresses()
             .expect("rpc call get_banned_addresses")
     }
 
+    pub fn try_get_banned_addresses(&self) -> Result<Vec<BannedAddr>, Error> {
+        self.inner().get_banned_addresses()
+    }
+
     pub fn set_ban(
         &self,
         address: String,
@@ -179,9 +402,20 @@ impl RpcClient {
         absolute: Option<bool>,
         reason: Option<String>,
     ) {
+        self.try_set_ban(address, command, ban_time, absolute, reason)
+            .expect("rpc call set_ban")
+    }
+
+    pub fn try_set_ban(
+        &self,
+        address: String,
+        command: String,
+        ban_time: Option<Timestamp>,
+        absolute: Option<bool>,
+        reason: Option<String>,
+    ) -> Result<(), Error> {
         self.inner()
             .set_ban(address, command, ban_time, absolute, reason)
-            .expect("rpc call set_ban")
     }
 
     pub fn get_block_template(
@@ -190,38 +424,74 @@ impl RpcClient {
         proposals_limit: Option<u64>,
         max_version: Option<CoreVersion>,
     ) -> BlockTemplate {
+        self.try_get_block_template(bytes_limit, proposals_limit, max_version)
+            .expect("rpc call get_block_template2021")
+    }
+
+    pub fn try_get_block_template(
+        &self,
+        bytes_limit: Option<u64>,
+        proposals_limit: Option<u64>,
+        max_version: Option<CoreVersion>,
+    ) -> Result<BlockTemplate, Error> {
         let bytes_limit = bytes_limit.map(Into::into);
         let proposals_limit = proposals_limit.map(Into::into);
         let max_version = max_version.map(Into::into);
-        self.inner2021
-            .get_block_template(bytes_limit, proposals_limit, max_version)
-            .expect("rpc call get_block_template2021")
+        if self.version.is_ckb2021() {
+            self.inner2021
+                .get_block_template(bytes_limit, proposals_limit, max_version)
+        } else {
+            self.call_compat(
+                "get_block_template",
+                (bytes_limit, proposals_limit, max_version),
+            )
+        }
     }
 
     pub fn submit_block(&self, work_id: String, block: Block) -> Result<Byte32, AnyError> {
-        self.inner2021
-            .submit_block(work_id, block)
-            .map(|x| x.pack())
+        self.try_submit_block(work_id, block).map_err(Into::into)
+    }
+
+    pub fn try_submit_block(&self, work_id: String, block: Block) -> Result<Byte32, Error> {
+        let hash: H256 = if self.version.is_ckb2021() {
+            self.inner2021.submit_block(work_id, block)?
+        } else {
+            self.call_compat("submit_block", (work_id, block))?
+        };
+        Ok(hash.pack())
     }
 
     pub fn get_blockchain_info(&self) -> ChainInfo {
-        self.inner()
-            .get_blockchain_info()
+        self.try_get_blockchain_info()
             .expect("rpc call get_blockchain_info")
     }
 
+    pub fn try_get_blockchain_info(&self) -> Result<ChainInfo, Error> {
+        self.inner().get_blockchain_info()
+    }
+
     pub fn get_block_median_time(&self, block_hash: Byte32) -> Option<Timestamp> {
-        self.inner()
-            .get_block_median_time(block_hash.unpack())
+        self.try_get_block_median_time(block_hash)
             .expect("rpc call get_block_median_time")
     }
 
+    pub fn try_get_block_median_time(
+        &self,
+        block_hash: Byte32,
+    ) -> Result<Option<Timestamp>, Error> {
+        self.inner().get_block_median_time(block_hash.unpack())
+    }
+
     pub fn send_transaction(&self, tx: Transaction) -> Byte32 {
         self.send_transaction_result(tx)
             .expect("rpc call send_transaction")
     }
 
     pub fn send_transaction_result(&self, tx: Transaction) -> Result<Byte32, AnyError> {
+        self.try_send_transaction(tx).map_err(Into::into)
+    }
+
+    pub fn try_send_transaction(&self, tx: Transaction) -> Result<Byte32, Error> {
         let ret = self
             .inner2021
             .send_transaction(tx, Some("passthrough".to_string()));
@@ -238,7 +508,7 @@ impl RpcClient {
         if let Ok(ref hash) = ret {
             let start_time = Instant::now();
             while start_time.elapsed() <= Duration::from_secs(20) {
-                if let Some(txstatus) = self.inner2021.get_transaction(hash.clone()).unwrap() {
+                if let Some(txstatus) = self.inner2021.get_transaction(hash.clone())? {
                     if txstatus.tx_status.status != ckb_jsonrpc_types::Status::Unknown {
                         break;
                     }
@@ -249,42 +519,65 @@ impl RpcClient {
         ret.map(|h256| h256.pack())
     }
 
-
     pub fn send_alert(&self, alert: Alert) {
-        self.inner().send_alert(alert).expect("rpc call send_alert")
+        self.try_send_alert(alert).expect("rpc call send_alert")
+    }
+
+    pub fn try_send_alert(&self, alert: Alert) -> Result<(), Error> {
+        self.inner().send_alert(alert)
     }
 
     pub fn tx_pool_info(&self) -> TxPoolInfo {
-        self.inner().tx_pool_info().expect("rpc call tx_pool_info")
+        self.try_tx_pool_info().expect("rpc call tx_pool_info")
+    }
+
+    pub fn try_tx_pool_info(&self) -> Result<TxPoolInfo, Error> {
+        self.inner().tx_pool_info()
     }
 
     pub fn add_node(&self, peer_id: String, address: String) {
-        self.inner()
-            .add_node(peer_id, address)
+        self.try_add_node(peer_id, address)
             .expect("rpc call add_node");
     }
 
+    pub fn try_add_node(&self, peer_id: String, address: String) -> Result<(), Error> {
+        self.inner().add_node(peer_id, address)
+    }
+
     pub fn remove_node(&self, peer_id: String) {
-        self.inner()
-            .remove_node(peer_id)
-            .expect("rpc call remove_node")
+        self.try_remove_node(peer_id).expect("rpc call remove_node")
+    }
+
+    pub fn try_remove_node(&self, peer_id: String) -> Result<(), Error> {
+        self.inner().remove_node(peer_id)
     }
 
     pub fn truncate(&self, target_tip_hash: Byte32) {
-        self.inner()
-            .truncate(target_tip_hash.unpack())
+        self.try_truncate(target_tip_hash)
             .expect("rpc call truncate")
     }
 
+    pub fn try_truncate(&self, target_tip_hash: Byte32) -> Result<(), Error> {
+        self.inner().truncate(target_tip_hash.unpack())
+    }
+
     pub fn calculate_dao_maximum_withdraw(
         &self,
         out_point: OutPoint,
         hash: Byte32,
     ) -> CoreCapacity {
+        self.try_calculate_dao_maximum_withdraw(out_point, hash)
+            .expect("rpc call calculate_dao_maximum_withdraw")
+    }
+
+    pub fn try_calculate_dao_maximum_withdraw(
+        &self,
+        out_point: OutPoint,
+        hash: Byte32,
+    ) -> Result<CoreCapacity, Error> {
         self.inner()
             .calculate_dao_maximum_withdraw(out_point, hash.unpack())
-            .expect("rpc call calculate_dao_maximum_withdraw")
-            .into()
+            .map(Into::into)
     }
 
     pub fn process_block_without_verify(
@@ -292,21 +585,205 @@ impl RpcClient {
         block: Block,
         should_broadcast: bool,
     ) -> Option<Byte32> {
-        self.inner2021
-            .process_block_without_verify(block, should_broadcast)
+        self.try_process_block_without_verify(block, should_broadcast)
             .expect("rpc call process_block_without_verify")
-            .map(|h256| h256.pack())
+    }
+
+    pub fn try_process_block_without_verify(
+        &self,
+        block: Block,
+        should_broadcast: bool,
+    ) -> Result<Option<Byte32>, Error> {
+        let hash: Option<H256> = if self.version.is_ckb2021() {
+            self.inner2021
+                .process_block_without_verify(block, should_broadcast)?
+        } else {
+            self.call_compat("process_block_without_verify", (block, should_broadcast))?
+        };
+        Ok(hash.map(|h256| h256.pack()))
+    }
+
+    /// Generate a block on top of the tip via the IntegrationTest RPC module
+    pub fn generate_block(&self) -> Byte32 {
+        self.try_generate_block().expect("rpc call generate_block")
+    }
+
+    pub fn try_generate_block(&self) -> Result<Byte32, Error> {
+        self.inner2021.generate_block().map(|h256| h256.pack())
+    }
+
+    /// Generate blocks for `num_epochs` epochs via the IntegrationTest RPC module, then return
+    /// the epoch of the tip
+    pub fn generate_epochs(
+        &self,
+        num_epochs: CoreEpochNumberWithFraction,
+    ) -> CoreEpochNumberWithFraction {
+        self.try_generate_epochs(num_epochs)
+            .expect("rpc call generate_epochs")
+    }
+
+    pub fn try_generate_epochs(
+        &self,
+        num_epochs: CoreEpochNumberWithFraction,
+    ) -> Result<CoreEpochNumberWithFraction, Error> {
+        self.inner2021
+            .generate_epochs(num_epochs.full_value().into())
+            .map(|epoch| CoreEpochNumberWithFraction::from_full_value(epoch.value()))
     }
 
     pub fn calculate_dao_field(&self, block_template: BlockTemplate) -> Result<Byte32, AnyError> {
-        assert!(self.ckb2021);
+        self.try_calculate_dao_field(block_template)
+            .map_err(Into::into)
+    }
+
+    pub fn try_calculate_dao_field(&self, block_template: BlockTemplate) -> Result<Byte32, Error> {
+        if !self.is_ckb2021() {
+            return Err(Error::Other(format!(
+                "rpc calculate_dao_field is not supported by ckb {}",
+                self.version
+            )));
+        }
         self.inner2021
             .calculate_dao_field(block_template)
             .map(Into::into)
     }
 
     pub fn get_raw_tx_pool(&self, verbose: Option<bool>) -> Result<RawTxPool, AnyError> {
-        assert!(self.ckb2021);
+        self.try_get_raw_tx_pool(verbose).map_err(Into::into)
+    }
+
+    pub fn try_get_raw_tx_pool(&self, verbose: Option<bool>) -> Result<RawTxPool, Error> {
+        if !self.is_ckb2021() {
+            return Err(Error::Other(format!(
+                "rpc get_raw_tx_pool is not supported by ckb {}",
+                self.version
+            )));
+        }
         self.inner2021.get_raw_tx_pool(verbose)
     }
+
+    pub fn get_indexer_tip(&self) -> Option<IndexerTip> {
+        self.try_get_indexer_tip()
+            .expect("rpc call get_indexer_tip")
+    }
+
+    pub fn try_get_indexer_tip(&self) -> Result<Option<IndexerTip>, Error> {
+        self.inner2021.get_indexer_tip()
+    }
+
+    /// Return one page of the live cells matching `search_key`. Pass the `last_cursor` of the
+    /// previous page as `after` to fetch the next page.
+    pub fn get_cells(
+        &self,
+        search_key: IndexerSearchKey,
+        order: IndexerOrder,
+        limit: u32,
+        after: Option<JsonBytes>,
+    ) -> IndexerPagination<IndexerCell> {
+        self.try_get_cells(search_key, order, limit, after)
+            .expect("rpc call get_cells")
+    }
+
+    pub fn try_get_cells(
+        &self,
+        search_key: IndexerSearchKey,
+        order: IndexerOrder,
+        limit: u32,
+        after: Option<JsonBytes>,
+    ) -> Result<IndexerPagination<IndexerCell>, Error> {
+        self.inner2021
+            .get_cells(search_key, order, limit.into(), after)
+    }
+
+    /// Return all the live cells matching `search_key`, walking through every page.
+    pub fn get_all_cells(&self, search_key: IndexerSearchKey) -> Vec<IndexerCell> {
+        self.try_get_all_cells(search_key)
+            .expect("rpc call get_cells")
+    }
+
+    pub fn try_get_all_cells(
+        &self,
+        search_key: IndexerSearchKey,
+    ) -> Result<Vec<IndexerCell>, Error> {
+        let mut cells = Vec::new();
+        let mut after = None;
+        loop {
+            let page = self.try_get_cells(
+                search_key.clone(),
+                IndexerOrder::Asc,
+                INDEXER_PAGE_SIZE,
+                after,
+            )?;
+            if page.objects.is_empty() {
+                break;
+            }
+            cells.extend(page.objects);
+            after = Some(page.last_cursor);
+        }
+        Ok(cells)
+    }
+
+    /// Return one page of the transactions matching `search_key`. Pass the `last_cursor` of the
+    /// previous page as `after` to fetch the next page.
+    pub fn get_transactions(
+        &self,
+        search_key: IndexerSearchKey,
+        order: IndexerOrder,
+        limit: u32,
+        after: Option<JsonBytes>,
+    ) -> IndexerPagination<IndexerTx> {
+        self.try_get_transactions(search_key, order, limit, after)
+            .expect("rpc call get_transactions")
+    }
+
+    pub fn try_get_transactions(
+        &self,
+        search_key: IndexerSearchKey,
+        order: IndexerOrder,
+        limit: u32,
+        after: Option<JsonBytes>,
+    ) -> Result<IndexerPagination<IndexerTx>, Error> {
+        self.inner2021
+            .get_transactions(search_key, order, limit.into(), after)
+    }
+
+    /// Return all the transactions matching `search_key`, walking through every page.
+    pub fn get_all_transactions(&self, search_key: IndexerSearchKey) -> Vec<IndexerTx> {
+        self.try_get_all_transactions(search_key)
+            .expect("rpc call get_transactions")
+    }
+
+    pub fn try_get_all_transactions(
+        &self,
+        search_key: IndexerSearchKey,
+    ) -> Result<Vec<IndexerTx>, Error> {
+        let mut transactions = Vec::new();
+        let mut after = None;
+        loop {
+            let page = self.try_get_transactions(
+                search_key.clone(),
+                IndexerOrder::Asc,
+                INDEXER_PAGE_SIZE,
+                after,
+            )?;
+            if page.objects.is_empty() {
+                break;
+            }
+            transactions.extend(page.objects);
+            after = Some(page.last_cursor);
+        }
+        Ok(transactions)
+    }
+
+    pub fn get_cells_capacity(&self, search_key: IndexerSearchKey) -> Option<IndexerCellsCapacity> {
+        self.try_get_cells_capacity(search_key)
+            .expect("rpc call get_cells_capacity")
+    }
+
+    pub fn try_get_cells_capacity(
+        &self,
+        search_key: IndexerSearchKey,
+    ) -> Result<Option<IndexerCellsCapacity>, Error> {
+        self.inner2021.get_cells_capacity(search_key)
+    }
 }
@@ -1,7 +1,7 @@
-use ckb_jsonrpc_types::{Alert, BannedAddr, Block, BlockNumber, BlockTemplate, BlockView, Byte32, Capacity, CellWithStatus, ChainInfo, Consensus, EpochNumber, EpochView, EstimateCycles, HeaderView, JsonBytes, LocalNode, OutPoint, RawTxPool, RemoteNode, Script, Timestamp, Transaction, TransactionWithStatusResponse, TxPoolInfo, Uint64, Version};
+use ckb_jsonrpc_types::{Alert, BannedAddr, Block, BlockNumber, BlockTemplate, BlockView, Byte32, Capacity, CellWithStatus, ChainInfo, Consensus, EpochNumber, EpochView, EstimateCycles, HeaderView, IndexerCell, IndexerCellsCapacity, IndexerOrder, IndexerPagination, IndexerSearchKey, IndexerTip, IndexerTx, JsonBytes, LocalNode, OutPoint, RawTxPool, RemoteNode, Timestamp, Transaction, TransactionWithStatusResponse, TxPoolInfo, Uint32, Uint64, Version};
 use ckb_types::H256;
 
-jsonrpc!(pub struct Inner2021 {
+jsonrpc!(pub struct Inner2021, pub async struct AsyncInner2021 {
     pub fn get_block(&self, _hash: H256) -> Option<BlockView>;
     pub fn get_fork_block(&self, _hash: H256) -> Option<BlockView>;
     pub fn get_block_by_number(&self, _number: BlockNumber) -> Option<BlockView>;
@@ -46,10 +46,28 @@ jsonrpc!(pub struct Inner2021 {
     pub fn remove_node(&self, peer_id: String) -> ();
     pub fn process_block_without_verify(&self, _data: Block, broadcast: bool) -> Option<H256>;
     pub fn truncate(&self, target_tip_hash: H256) -> ();
-    pub fn generate_block(&self, block_assembler_script: Option<Script>, block_assembler_message: Option<JsonBytes>) -> H256;
+    pub fn generate_block(&self) -> H256;
+    pub fn generate_epochs(&self, num_epochs: Uint64) -> Uint64;
     pub fn generate_block_with_template(&self, block_template: BlockTemplate) -> H256;
     pub fn calculate_dao_field(&self, block_template: BlockTemplate) -> Byte32;
     pub fn get_raw_tx_pool(&self, verbose: Option<bool>) -> RawTxPool;
 
     pub fn calculate_dao_maximum_withdraw(&self, _out_point: OutPoint, _hash: H256) -> Capacity;
+
+    pub fn get_indexer_tip(&self) -> Option<IndexerTip>;
+    pub fn get_cells(
+        &self,
+        search_key: IndexerSearchKey,
+        order: IndexerOrder,
+        limit: Uint32,
+        after: Option<JsonBytes>
+    ) -> IndexerPagination<IndexerCell>;
+    pub fn get_transactions(
+        &self,
+        search_key: IndexerSearchKey,
+        order: IndexerOrder,
+        limit: Uint32,
+        after: Option<JsonBytes>
+    ) -> IndexerPagination<IndexerTx>;
+    pub fn get_cells_capacity(&self, search_key: IndexerSearchKey) -> Option<IndexerCellsCapacity>;
 });
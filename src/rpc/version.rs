@@ -0,0 +1,102 @@
+use std::fmt;
+
+/// The release version of a ckb node, detected via `local_node_info`. `RpcClient` picks the
+/// RPC compatibility shims by it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CkbVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl CkbVersion {
+    /// The first release supporting ckb2021, whose headers carry `extra_hash` instead of
+    /// `uncles_hash` and whose consensus carries `hardfork_features`
+    pub const CKB2021: CkbVersion = CkbVersion::new(0, 44, 0);
+    /// The first release replacing RPC `dry_run_transaction` with `estimate_cycles`
+    pub const ESTIMATE_CYCLES: CkbVersion = CkbVersion::new(0, 105, 0);
+    /// The release whose RPC types this crate is built against, assumed before detection
+    pub const LATEST: CkbVersion = CkbVersion::new(0, 109, 0);
+
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse the version reported by `local_node_info`, e.g. "0.109.0 (a3d8d0c 2023-04-12)",
+    /// "v0.43.2" or "0.110.0-rc1".
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.split_whitespace().next()?;
+        let version = version.trim_start_matches('v');
+        let version = version.split(|c| c == '-' || c == '+').next()?;
+        let mut numbers = version.split('.').map(|number| number.parse::<u64>());
+        let major = numbers.next()?.ok()?;
+        let minor = numbers.next()?.ok()?;
+        let patch = numbers.next().unwrap_or(Ok(0)).ok()?;
+        Some(Self::new(major, minor, patch))
+    }
+
+    pub fn is_ckb2021(&self) -> bool {
+        *self >= Self::CKB2021
+    }
+
+    pub fn has_estimate_cycles(&self) -> bool {
+        *self >= Self::ESTIMATE_CYCLES
+    }
+}
+
+impl Default for CkbVersion {
+    fn default() -> Self {
+        Self::LATEST
+    }
+}
+
+impl fmt::Display for CkbVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CkbVersion;
+
+    #[test]
+    fn test_parse_with_commit_suffix() {
+        assert_eq!(
+            CkbVersion::parse("0.109.0 (a3d8d0c 2023-04-12)"),
+            Some(CkbVersion::new(0, 109, 0))
+        );
+        assert_eq!(
+            CkbVersion::parse("0.110.0-rc1 (e4a8d7b 2023-05-01)"),
+            Some(CkbVersion::new(0, 110, 0))
+        );
+    }
+
+    #[test]
+    fn test_parse_without_commit_suffix() {
+        assert_eq!(
+            CkbVersion::parse("0.109.0"),
+            Some(CkbVersion::new(0, 109, 0))
+        );
+        assert_eq!(
+            CkbVersion::parse("v0.43.2"),
+            Some(CkbVersion::new(0, 43, 2))
+        );
+        assert_eq!(
+            CkbVersion::parse("0.110.0-rc1"),
+            Some(CkbVersion::new(0, 110, 0))
+        );
+        assert_eq!(CkbVersion::parse("0.44"), Some(CkbVersion::new(0, 44, 0)));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(CkbVersion::parse(""), None);
+        assert_eq!(CkbVersion::parse("unknown"), None);
+        assert_eq!(CkbVersion::parse("0.x.0"), None);
+    }
+}
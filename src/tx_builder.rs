@@ -0,0 +1,229 @@
+use crate::{Error, Node, User};
+use ckb_types::{
+    bytes::Bytes,
+    core::{cell::CellMeta, Capacity, FeeRate, TransactionBuilder, TransactionView},
+    packed::{Byte32, CellDep, CellInput, CellOutput, Script},
+    prelude::*,
+};
+
+/// The default fee rate of `TxBuilder`, in shannons per KB. It equals to the default
+/// `min_fee_rate` of ckb tx-pool.
+pub const DEFAULT_FEE_RATE: u64 = 1000;
+
+/// The lock of the input cells collected by `TxBuilder`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockKind {
+    /// `Node::always_success_script`, e.g. the cellbase cells mined by the node
+    AlwaysSuccess,
+    /// The single_secp256k1 locks of the user, via type, data and data1, signed by the user's
+    /// private key
+    SingleSecp256k1,
+}
+
+/// Build a transaction from the desired outputs. It collects the spendable input cells of
+/// `lock_kind` from the node, adds the required cell deps, creates a change output and signs.
+///
+/// The input cells are looked up via the built-in indexer, so the node must run with
+/// `NodeOptions::indexer(true)`.
+///
+/// ```ignore
+/// let tx = TxBuilder::new(&node)
+///     .user(&user)
+///     .lock_kind(LockKind::SingleSecp256k1)
+///     .output(output, Bytes::new())
+///     .fee_rate(2000)
+///     .build();
+/// node.submit_transaction(&tx);
+/// ```
+pub struct TxBuilder<'a> {
+    node: &'a Node,
+    user: Option<&'a User>,
+    lock_kind: LockKind,
+    inputs: Vec<CellMeta>,
+    outputs: Vec<(CellOutput, Bytes)>,
+    cell_deps: Vec<CellDep>,
+    header_deps: Vec<Byte32>,
+    fee_rate: u64,
+    change_lock: Option<Script>,
+}
+
+impl<'a> TxBuilder<'a> {
+    pub fn new(node: &'a Node) -> Self {
+        Self {
+            node,
+            user: None,
+            lock_kind: LockKind::AlwaysSuccess,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            cell_deps: Vec::new(),
+            header_deps: Vec::new(),
+            fee_rate: DEFAULT_FEE_RATE,
+            change_lock: None,
+        }
+    }
+
+    /// The user who owns and signs the inputs, required by `LockKind::SingleSecp256k1`
+    pub fn user(mut self, user: &'a User) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    pub fn lock_kind(mut self, lock_kind: LockKind) -> Self {
+        self.lock_kind = lock_kind;
+        self
+    }
+
+    /// Spend `cell` before the collected inputs. It must be locked by the lock of `lock_kind`
+    /// so that it is signed together.
+    pub fn input(mut self, cell: CellMeta) -> Self {
+        self.inputs.push(cell);
+        self
+    }
+
+    pub fn output(mut self, output: CellOutput, data: Bytes) -> Self {
+        self.outputs.push((output, data));
+        self
+    }
+
+    pub fn outputs<I: IntoIterator<Item = (CellOutput, Bytes)>>(mut self, outputs: I) -> Self {
+        self.outputs.extend(outputs);
+        self
+    }
+
+    /// Extra cell deps besides the lock's, e.g. the cell deps of the output type scripts
+    pub fn cell_dep(mut self, cell_dep: CellDep) -> Self {
+        self.cell_deps.push(cell_dep);
+        self
+    }
+
+    pub fn header_dep(mut self, header_dep: Byte32) -> Self {
+        self.header_deps.push(header_dep);
+        self
+    }
+
+    /// Fee rate in shannons per KB
+    pub fn fee_rate(mut self, fee_rate: u64) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    /// The lock of the change output, default is the lock of the inputs
+    pub fn change_lock(mut self, change_lock: Script) -> Self {
+        self.change_lock = Some(change_lock);
+        self
+    }
+
+    pub fn build(self) -> TransactionView {
+        self.try_build().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_build(self) -> Result<TransactionView, Error> {
+        let (candidates, default_change_lock) = match self.lock_kind {
+            LockKind::AlwaysSuccess => {
+                let lock = self.node.always_success_script();
+                let cells = self.node.try_get_spendable_cells_by_lock_script(&lock)?;
+                (cells, lock)
+            }
+            LockKind::SingleSecp256k1 => {
+                let user = self.user.ok_or_else(|| {
+                    Error::Other("TxBuilder: LockKind::SingleSecp256k1 requires a user".to_string())
+                })?;
+                let cells = user.try_get_spendable_single_secp256k1_cells(self.node)?;
+                (cells, user.single_secp256k1_lock_script_via_type())
+            }
+        };
+        let change_lock = self.change_lock.clone().unwrap_or(default_change_lock);
+        let outputs_capacity = self.outputs.iter().try_fold(0u64, |sum, (output, _)| {
+            sum.checked_add(output.capacity().unpack())
+                .ok_or_else(|| Error::Other("outputs capacity overflow".to_string()))
+        })?;
+
+        let mut inputs = self.inputs.clone();
+        let mut inputs_capacity = inputs
+            .iter()
+            .map(|cell| cell.capacity().as_u64())
+            .sum::<u64>();
+        let preset_out_points = inputs
+            .iter()
+            .map(|cell| cell.out_point.clone())
+            .collect::<Vec<_>>();
+        let candidates = candidates
+            .into_iter()
+            .filter(|cell| !preset_out_points.contains(&cell.out_point));
+        // Try the preset inputs alone first, then collect the candidates one by one
+        for cell in ::std::iter::once(None).chain(candidates.map(Some)) {
+            if let Some(cell) = cell {
+                inputs_capacity += cell.capacity().as_u64();
+                inputs.push(cell);
+            }
+            if inputs.is_empty() || inputs_capacity < outputs_capacity {
+                continue;
+            }
+
+            // Estimate the fee over the transaction signed with a zero-capacity change output,
+            // the size of which equals to the final one.
+            let change_output = CellOutput::new_builder().lock(change_lock.clone()).build();
+            let change_occupied = change_output
+                .occupied_capacity(Capacity::zero())
+                .map_err(|err| Error::Other(err.to_string()))?
+                .as_u64();
+            let signed = self.sign(self.assemble(&inputs, change_output.clone()), &inputs);
+            let fee = FeeRate::from_u64(self.fee_rate)
+                .fee(signed.data().serialized_size_in_block() as u64)
+                .as_u64();
+            let change_capacity = inputs_capacity - outputs_capacity;
+            if change_capacity < fee + change_occupied {
+                continue;
+            }
+
+            let change_output = change_output
+                .as_builder()
+                .capacity((change_capacity - fee).pack())
+                .build();
+            return Ok(self.sign(self.assemble(&inputs, change_output), &inputs));
+        }
+        Err(Error::Other(format!(
+            "insufficient capacity of {:?} cells, collected: {}, outputs: {}",
+            self.lock_kind, inputs_capacity, outputs_capacity,
+        )))
+    }
+
+    fn lock_cell_dep(&self) -> CellDep {
+        match self.lock_kind {
+            LockKind::AlwaysSuccess => self.node.always_success_cell_dep(),
+            LockKind::SingleSecp256k1 => self
+                .user
+                .expect("checked by try_build")
+                .single_secp256k1_cell_dep(),
+        }
+    }
+
+    fn assemble(&self, inputs: &[CellMeta], change_output: CellOutput) -> TransactionView {
+        let mut builder = TransactionBuilder::default()
+            .cell_dep(self.lock_cell_dep())
+            .cell_deps(self.cell_deps.clone())
+            .header_deps(self.header_deps.clone())
+            .inputs(
+                inputs
+                    .iter()
+                    .map(|cell| CellInput::new(cell.out_point.clone(), 0)),
+            );
+        for (output, data) in self.outputs.iter() {
+            builder = builder.output(output.clone()).output_data(data.pack());
+        }
+        builder = builder
+            .output(change_output)
+            .output_data(Bytes::new().pack());
+        builder.build()
+    }
+
+    fn sign(&self, unsigned: TransactionView, inputs: &[CellMeta]) -> TransactionView {
+        match self.lock_kind {
+            LockKind::AlwaysSuccess => unsigned,
+            LockKind::SingleSecp256k1 => self
+                .user
+                .expect("checked by try_build")
+                .single_secp256k1_sign_transaction(&unsigned, inputs),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::node::minimal_unlock_point;
+use crate::util::since_from_absolute_epoch_number_with_fraction;
+use crate::{Error, LockKind, Node, TxBuilder, User, DEFAULT_FEE_RATE};
+use ckb_types::{
+    bytes::Bytes,
+    core::{cell::CellMeta, Capacity, FeeRate, HeaderView, TransactionBuilder, TransactionView},
+    packed::{CellInput, CellOutput, WitnessArgs},
+    prelude::*,
+};
+use std::convert::TryInto;
+
+impl User {
+    /// Deposit `capacity` into NervosDAO, locked by the user's single_secp256k1 lock.
+    pub fn dao_deposit_transaction(&self, node: &Node, capacity: Capacity) -> TransactionView {
+        self.try_dao_deposit_transaction(node, capacity)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_dao_deposit_transaction(
+        &self,
+        node: &Node,
+        capacity: Capacity,
+    ) -> Result<TransactionView, Error> {
+        let output = CellOutput::new_builder()
+            .lock(self.single_secp256k1_lock_script_via_type())
+            .type_(Some(node.dao_type_script()).pack())
+            .capacity(capacity.pack())
+            .build();
+        TxBuilder::new(node)
+            .user(self)
+            .lock_kind(LockKind::SingleSecp256k1)
+            .output(output, Bytes::from(vec![0u8; 8]))
+            .cell_dep(node.dao_cell_dep())
+            .try_build()
+    }
+
+    /// Phase 1 of withdrawing: transform the deposited cell into a prepared cell, whose data is
+    /// the deposit block number. The fee is paid by the other cells of the user.
+    pub fn dao_prepare_transaction(&self, node: &Node, deposited: &CellMeta) -> TransactionView {
+        self.try_dao_prepare_transaction(node, deposited)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_dao_prepare_transaction(
+        &self,
+        node: &Node,
+        deposited: &CellMeta,
+    ) -> Result<TransactionView, Error> {
+        let deposit_info = deposited
+            .transaction_info
+            .as_ref()
+            .ok_or_else(|| Error::Other("deposited cell is not committed".to_string()))?;
+        let output_data = Bytes::from(deposit_info.block_number.to_le_bytes().to_vec());
+        TxBuilder::new(node)
+            .user(self)
+            .lock_kind(LockKind::SingleSecp256k1)
+            .input(deposited.clone())
+            .output(deposited.cell_output.clone(), output_data)
+            .cell_dep(node.dao_cell_dep())
+            .header_dep(deposit_info.block_hash.clone())
+            .try_build()
+    }
+
+    /// Phase 2 of withdrawing: withdraw the prepared cell with the interest to the user's
+    /// single_secp256k1 lock, since the minimal unlock epoch.
+    pub fn dao_withdraw_transaction(&self, node: &Node, prepared: &CellMeta) -> TransactionView {
+        self.try_dao_withdraw_transaction(node, prepared)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_dao_withdraw_transaction(
+        &self,
+        node: &Node,
+        prepared: &CellMeta,
+    ) -> Result<TransactionView, Error> {
+        let prepare_info = prepared
+            .transaction_info
+            .as_ref()
+            .ok_or_else(|| Error::Other("prepared cell is not committed".to_string()))?;
+        let deposit_number = prepared
+            .mem_cell_data
+            .as_ref()
+            .and_then(|data| data.as_ref().try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or_else(|| Error::Other("invalid data of prepared cell".to_string()))?;
+        let deposit_header = node.try_get_header_by_number(deposit_number)?;
+        let prepare_header: HeaderView = node
+            .rpc_client()
+            .try_get_header(prepare_info.block_hash.clone())?
+            .ok_or_else(|| Error::NotFound(format!("header {:#x}", prepare_info.block_hash)))?
+            .into();
+        let unlock_point = minimal_unlock_point(&deposit_header, &prepare_header);
+        let since = since_from_absolute_epoch_number_with_fraction(unlock_point);
+        let maximum_withdraw = node
+            .rpc_client()
+            .try_calculate_dao_maximum_withdraw(
+                prepared.out_point.clone().into(),
+                prepare_header.hash(),
+            )?
+            .as_u64();
+
+        // The witness of the prepared cell carries the index of the deposit header inside
+        // header_deps as input_type
+        let witness = WitnessArgs::new_builder()
+            .input_type(Some(Bytes::from(0u64.to_le_bytes().to_vec())).pack())
+            .build();
+        let assemble = |capacity: u64| {
+            let unsigned = TransactionBuilder::default()
+                .cell_dep(node.dao_cell_dep())
+                .cell_dep(self.single_secp256k1_cell_dep())
+                .header_dep(deposit_header.hash())
+                .header_dep(prepare_header.hash())
+                .input(CellInput::new(prepared.out_point.clone(), since))
+                .output(
+                    CellOutput::new_builder()
+                        .lock(self.single_secp256k1_lock_script_via_type())
+                        .capacity(capacity.pack())
+                        .build(),
+                )
+                .output_data(Bytes::new().pack())
+                .witness(witness.as_bytes().pack())
+                .build();
+            self.single_secp256k1_sign_transaction(&unsigned, &[prepared.clone()])
+        };
+        let fee = FeeRate::from_u64(DEFAULT_FEE_RATE)
+            .fee(assemble(maximum_withdraw).data().serialized_size_in_block() as u64)
+            .as_u64();
+        let capacity = maximum_withdraw.checked_sub(fee).ok_or_else(|| {
+            Error::Other(format!(
+                "maximum withdraw {} is less than fee {}",
+                maximum_withdraw, fee
+            ))
+        })?;
+        Ok(assemble(capacity))
+    }
+}
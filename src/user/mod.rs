@@ -1,3 +1,6 @@
+mod dao;
+pub mod multisig;
+mod sighash;
 pub mod single_secp256k1;
 
 use ckb_crypto::secp::Privkey;
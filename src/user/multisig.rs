@@ -0,0 +1,261 @@
+use crate::user::sighash;
+use crate::{
+    GENESIS_DEP_GROUP_TRANSACTION_INDEX, GENESIS_MULTISIG_DEP_GROUP_CELL_INDEX, MULTISIG_TYPE_HASH,
+};
+use ckb_crypto::secp::Privkey;
+use ckb_hash::blake2b_256;
+use ckb_types::{
+    bytes::{BufMut, Bytes, BytesMut},
+    core::{cell::CellMeta, BlockView, DepType, ScriptHashType, TransactionView},
+    packed::{CellDep, OutPoint, Script},
+    prelude::*,
+    H160,
+};
+
+/// The M-of-N configuration of secp256k1_blake160_multisig_all.
+#[derive(Clone, Debug)]
+pub struct MultisigConfig {
+    require_first_n: u8,
+    threshold: u8,
+    pubkey_hashes: Vec<H160>,
+}
+
+impl MultisigConfig {
+    /// `threshold` signatures are required, and the first `require_first_n` of them must be
+    /// signed by the first `require_first_n` pubkeys, in order.
+    pub fn new(require_first_n: u8, threshold: u8, pubkey_hashes: Vec<H160>) -> Self {
+        assert!(
+            !pubkey_hashes.is_empty() && pubkey_hashes.len() <= u8::MAX as usize,
+            "invalid pubkeys count {}",
+            pubkey_hashes.len()
+        );
+        assert!(
+            threshold > 0 && threshold as usize <= pubkey_hashes.len(),
+            "invalid threshold {} of {} pubkeys",
+            threshold,
+            pubkey_hashes.len()
+        );
+        assert!(
+            require_first_n <= threshold,
+            "require_first_n {} exceeds threshold {}",
+            require_first_n,
+            threshold
+        );
+        Self {
+            require_first_n,
+            threshold,
+            pubkey_hashes,
+        }
+    }
+
+    pub fn require_first_n(&self) -> u8 {
+        self.require_first_n
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn pubkey_hashes(&self) -> &[H160] {
+        &self.pubkey_hashes
+    }
+
+    /// The multisig script, `S | R | M | N | blake160(Pubkey1) | ... | blake160(PubkeyN)`
+    pub fn multisig_script(&self) -> Bytes {
+        let mut script = BytesMut::with_capacity(4 + 20 * self.pubkey_hashes.len());
+        script.put_u8(0);
+        script.put_u8(self.require_first_n);
+        script.put_u8(self.threshold);
+        script.put_u8(self.pubkey_hashes.len() as u8);
+        for pubkey_hash in self.pubkey_hashes.iter() {
+            script.put_slice(pubkey_hash.as_bytes());
+        }
+        script.freeze()
+    }
+
+    pub fn hash160(&self) -> H160 {
+        H160::from_slice(&blake2b_256(self.multisig_script())[0..20]).unwrap()
+    }
+}
+
+/// A user of the genesis secp256k1_blake160_multisig_all lock, holding some or all of the
+/// private keys of the multisig config.
+///
+/// The signing is split in two phases so that each key holder can sign separately:
+/// `multisig_sign_partially` produces one signature per key, and
+/// `multisig_assemble_witnesses` puts the collected signatures into the witness.
+#[derive(Clone)]
+pub struct MultisigUser {
+    // a workaround to get out-point of system script cells
+    genesis_block: BlockView,
+    config: MultisigConfig,
+    since: Option<u64>,
+    privkeys: Vec<Privkey>,
+}
+
+impl MultisigUser {
+    /// Every key of `privkeys` must belong to `config`.
+    pub fn new(genesis_block: BlockView, config: MultisigConfig, privkeys: Vec<Privkey>) -> Self {
+        let user = Self {
+            genesis_block,
+            config,
+            since: None,
+            privkeys,
+        };
+        for (privkey_index, privkey) in user.privkeys.iter().enumerate() {
+            assert!(
+                user.privkey_pubkey_index(privkey).is_some(),
+                "privkey {} does not belong to the multisig config",
+                privkey_index
+            );
+        }
+        user
+    }
+
+    /// Embed the since value into the lock args. The inputs must carry a `since` not less
+    /// than it.
+    pub fn with_since(mut self, since: u64) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn config(&self) -> &MultisigConfig {
+        &self.config
+    }
+
+    pub fn since(&self) -> Option<u64> {
+        self.since
+    }
+
+    pub fn privkeys(&self) -> &[Privkey] {
+        &self.privkeys
+    }
+
+    /// `blake160(multisig_script) | since`, the since is encoded in little endian
+    pub fn multisig_lock_args(&self) -> Bytes {
+        let mut args = BytesMut::with_capacity(28);
+        args.put_slice(self.config.hash160().as_bytes());
+        if let Some(since) = self.since {
+            args.put_u64_le(since);
+        }
+        args.freeze()
+    }
+
+    pub fn multisig_lock_script(&self) -> Script {
+        Script::new_builder()
+            .hash_type(ScriptHashType::Type.into())
+            .code_hash(MULTISIG_TYPE_HASH.pack())
+            .args(self.multisig_lock_args().pack())
+            .build()
+    }
+
+    pub fn multisig_out_point(&self) -> OutPoint {
+        OutPoint::new_builder()
+            .tx_hash(
+                self.genesis_block
+                    .transaction(GENESIS_DEP_GROUP_TRANSACTION_INDEX)
+                    .expect("index genesis dep-group transaction")
+                    .hash(),
+            )
+            .index(GENESIS_MULTISIG_DEP_GROUP_CELL_INDEX.pack())
+            .build()
+    }
+
+    pub fn multisig_cell_dep(&self) -> CellDep {
+        CellDep::new_builder()
+            .out_point(self.multisig_out_point())
+            .dep_type(DepType::DepGroup.into())
+            .build()
+    }
+
+    /// Sign the multisig lock group of `tx` with the private key at `privkey_index`. Return the
+    /// index of the key in the multisig config and the 65-bytes signature, or `None` if `tx`
+    /// spends no cell of this multisig lock or the user holds no key at `privkey_index`.
+    ///
+    /// `input_cells` are the resolved input cells of `tx`, in the same order as the inputs.
+    pub fn multisig_sign_partially(
+        &self,
+        tx: &TransactionView,
+        input_cells: &[CellMeta],
+        privkey_index: usize,
+    ) -> Option<(usize, Bytes)> {
+        let privkey = self.privkeys.get(privkey_index)?;
+        let pubkey_index = self.privkey_pubkey_index(privkey)?;
+        let group = self.lock_group(input_cells)?;
+        let witnesses = sighash::padded_witnesses(tx);
+        let message = sighash::sighash_all_message(tx, &witnesses, &group, self.lock_placeholder());
+        let sig = privkey.sign_recoverable(&message).expect("sign");
+        Some((pubkey_index, Bytes::from(sig.serialize())))
+    }
+
+    /// Put the signatures collected by `multisig_sign_partially` into the first witness of the
+    /// multisig lock group, keeping its `input_type` and `output_type`.
+    ///
+    /// The signatures are ordered by the key index, as the first `require_first_n` ones are
+    /// checked in order. Only the first `threshold` signatures are kept.
+    pub fn multisig_assemble_witnesses(
+        &self,
+        tx: &TransactionView,
+        input_cells: &[CellMeta],
+        mut signatures: Vec<(usize, Bytes)>,
+    ) -> TransactionView {
+        let group = match self.lock_group(input_cells) {
+            Some(group) => group,
+            None => return tx.clone(),
+        };
+        assert!(
+            signatures.len() >= self.config.threshold as usize,
+            "insufficient signatures, {} < threshold {}",
+            signatures.len(),
+            self.config.threshold
+        );
+        signatures.sort_by_key(|(pubkey_index, _)| *pubkey_index);
+        signatures.dedup_by_key(|(pubkey_index, _)| *pubkey_index);
+
+        let mut lock = BytesMut::from(self.config.multisig_script().as_ref());
+        for (_, signature) in signatures.into_iter().take(self.config.threshold as usize) {
+            lock.put_slice(&signature);
+        }
+        let mut witnesses = sighash::padded_witnesses(tx);
+        sighash::set_witness_lock(&mut witnesses, group[0], lock.freeze());
+        sighash::with_witnesses(tx, witnesses)
+    }
+
+    /// Sign with all the held private keys and assemble the witness. The user must hold at
+    /// least `threshold` keys.
+    pub fn multisig_sign_transaction(
+        &self,
+        tx: &TransactionView,
+        input_cells: &[CellMeta],
+    ) -> TransactionView {
+        let signatures = (0..self.privkeys.len())
+            .filter_map(|privkey_index| {
+                self.multisig_sign_partially(tx, input_cells, privkey_index)
+            })
+            .collect::<Vec<_>>();
+        self.multisig_assemble_witnesses(tx, input_cells, signatures)
+    }
+
+    // The index of the pubkey of `privkey` in the multisig config
+    fn privkey_pubkey_index(&self, privkey: &Privkey) -> Option<usize> {
+        let pubkey = privkey.pubkey().ok()?;
+        let pubkey_hash = H160::from_slice(&blake2b_256(pubkey.serialize())[0..20]).unwrap();
+        self.config
+            .pubkey_hashes
+            .iter()
+            .position(|hash| hash == &pubkey_hash)
+    }
+
+    fn lock_group(&self, input_cells: &[CellMeta]) -> Option<Vec<usize>> {
+        let lock_hash = self.multisig_lock_script().calc_script_hash();
+        sighash::group_inputs_by_lock(input_cells, |hash| hash == &lock_hash)
+            .into_iter()
+            .next()
+    }
+
+    fn lock_placeholder(&self) -> Bytes {
+        let mut placeholder = BytesMut::from(self.config.multisig_script().as_ref());
+        placeholder.put_slice(&vec![0u8; 65 * self.config.threshold as usize]);
+        placeholder.freeze()
+    }
+}
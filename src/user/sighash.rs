@@ -0,0 +1,102 @@
+use ckb_types::{
+    bytes::Bytes,
+    core::{cell::CellMeta, TransactionView},
+    packed::{Byte32, WitnessArgs},
+    prelude::*,
+    H256,
+};
+
+/// Group the input indexes by lock script hash, in the order of their first appearance. Only
+/// the locks accepted by `is_own_lock` are grouped.
+pub(crate) fn group_inputs_by_lock<F>(input_cells: &[CellMeta], is_own_lock: F) -> Vec<Vec<usize>>
+where
+    F: Fn(&Byte32) -> bool,
+{
+    let mut groups: Vec<(Byte32, Vec<usize>)> = Vec::new();
+    for (index, cell) in input_cells.iter().enumerate() {
+        let lock_hash = cell.cell_output.lock().calc_script_hash();
+        if !is_own_lock(&lock_hash) {
+            continue;
+        }
+        match groups.iter_mut().find(|(hash, _)| hash == &lock_hash) {
+            Some((_, indexes)) => indexes.push(index),
+            None => groups.push((lock_hash, vec![index])),
+        }
+    }
+    groups.into_iter().map(|(_, indexes)| indexes).collect()
+}
+
+/// Return the raw witnesses of `tx`, padded with empty witnesses up to the inputs count.
+pub(crate) fn padded_witnesses(tx: &TransactionView) -> Vec<Bytes> {
+    let mut witnesses = tx
+        .witnesses()
+        .into_iter()
+        .map(|witness| witness.raw_data())
+        .collect::<Vec<_>>();
+    if witnesses.len() < tx.inputs().len() {
+        witnesses.resize(tx.inputs().len(), Bytes::new());
+    }
+    witnesses
+}
+
+/// Parse the witness as `WitnessArgs`, an empty witness is parsed as the default one.
+pub(crate) fn parse_witness_args(witness: &Bytes) -> WitnessArgs {
+    if witness.is_empty() {
+        WitnessArgs::default()
+    } else {
+        WitnessArgs::from_slice(witness).expect("witness is WitnessArgs")
+    }
+}
+
+/// Return the sighash-all message of the lock group `group`, per the CKB system scripts:
+/// the transaction hash, the first witness of the group with the lock replaced by the
+/// placeholder, the other witnesses of the group and the witnesses beyond the inputs.
+pub(crate) fn sighash_all_message(
+    tx: &TransactionView,
+    witnesses: &[Bytes],
+    group: &[usize],
+    lock_placeholder: Bytes,
+) -> H256 {
+    let witness_for_digest = parse_witness_args(&witnesses[group[0]])
+        .as_builder()
+        .lock(Some(lock_placeholder).pack())
+        .build()
+        .as_bytes();
+
+    let mut blake2b = ckb_hash::new_blake2b();
+    let mut message = [0u8; 32];
+    blake2b.update(&tx.hash().raw_data());
+    blake2b.update(&(witness_for_digest.len() as u64).to_le_bytes());
+    blake2b.update(&witness_for_digest);
+    let other_indexes = group[1..]
+        .iter()
+        .copied()
+        .chain(tx.inputs().len()..witnesses.len());
+    for index in other_indexes {
+        let witness = &witnesses[index];
+        blake2b.update(&(witness.len() as u64).to_le_bytes());
+        blake2b.update(witness);
+    }
+    blake2b.finalize(&mut message);
+    H256::from(message)
+}
+
+/// Replace the lock of the witness at `index`, keeping its `input_type` and `output_type`.
+pub(crate) fn set_witness_lock(witnesses: &mut [Bytes], index: usize, lock: Bytes) {
+    witnesses[index] = parse_witness_args(&witnesses[index])
+        .as_builder()
+        .lock(Some(lock).pack())
+        .build()
+        .as_bytes();
+}
+
+pub(crate) fn with_witnesses(tx: &TransactionView, witnesses: Vec<Bytes>) -> TransactionView {
+    tx.as_advanced_builder()
+        .set_witnesses(
+            witnesses
+                .into_iter()
+                .map(|witness| witness.pack())
+                .collect(),
+        )
+        .build()
+}
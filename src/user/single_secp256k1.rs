@@ -1,11 +1,11 @@
+use crate::user::sighash;
 use crate::{
-    Node, User, GENESIS_DEP_GROUP_TRANSACTION_INDEX, GENESIS_SIGHASH_ALL_DEP_GROUP_CELL_INDEX,
-    SIGHASH_ALL_DATA_HASH, SIGHASH_ALL_TYPE_HASH,
+    Error, Node, User, GENESIS_DEP_GROUP_TRANSACTION_INDEX,
+    GENESIS_SIGHASH_ALL_DEP_GROUP_CELL_INDEX, SIGHASH_ALL_DATA_HASH, SIGHASH_ALL_TYPE_HASH,
 };
 use ckb_crypto::secp::{Message, Pubkey, Signature};
 use ckb_hash::blake2b_256;
 use ckb_types::core::cell::CellMeta;
-use ckb_types::core::EpochNumberWithFraction;
 use ckb_types::{
     bytes::Bytes,
     core::{DepType, ScriptHashType, TransactionView},
@@ -71,6 +71,9 @@ impl User {
         }
     }
 
+    /// Return the signed witness of a lock group consisting of a single input, whose
+    /// witness is the only one of the transaction. Use `single_secp256k1_sign_transaction` for
+    /// the general cases.
     pub fn single_secp256k1_signed_witness(&self, tx: &TransactionView) -> WitnessArgs {
         if let Some(ref privkey) = self.single_secp256k1_privkey {
             let tx_hash = tx.hash();
@@ -96,6 +99,45 @@ impl User {
         }
     }
 
+    /// Sign all the inputs of `tx` that are locked by this user's single_secp256k1 locks, and
+    /// return the fully witnessed transaction.
+    ///
+    /// `input_cells` are the resolved input cells of `tx`, in the same order as the inputs. The
+    /// inputs are grouped by lock script hash and each group is signed in sighash-all mode:
+    /// the digest covers the transaction hash, all the witnesses of the group and the witnesses
+    /// beyond the inputs. The `input_type` and `output_type` of the existing witnesses are kept.
+    pub fn single_secp256k1_sign_transaction(
+        &self,
+        tx: &TransactionView,
+        input_cells: &[CellMeta],
+    ) -> TransactionView {
+        assert_eq!(
+            tx.inputs().len(),
+            input_cells.len(),
+            "input_cells should be resolved from the inputs of tx"
+        );
+        let own_lock_hashes = [
+            self.single_secp256k1_lock_script_via_type(),
+            self.single_secp256k1_lock_script_via_data(),
+            self.single_secp256k1_lock_script_via_data1(),
+        ]
+        .iter()
+        .map(|lock| lock.calc_script_hash())
+        .collect::<Vec<_>>();
+        let groups = sighash::group_inputs_by_lock(input_cells, |lock_hash| {
+            own_lock_hashes.contains(lock_hash)
+        });
+
+        let mut witnesses = sighash::padded_witnesses(tx);
+        for group in groups {
+            let placeholder = Bytes::from(vec![0u8; 65]);
+            let message = sighash::sighash_all_message(tx, &witnesses, &group, placeholder);
+            let sig = self.sign_recoverable(&message);
+            sighash::set_witness_lock(&mut witnesses, group[0], Bytes::from(sig.serialize()));
+        }
+        sighash::with_witnesses(tx, witnesses)
+    }
+
     pub fn sign_recoverable(&self, message: &Message) -> Signature {
         if let Some(ref privkey) = self.single_secp256k1_privkey {
             privkey.sign_recoverable(message).expect("sign")
@@ -104,55 +146,26 @@ impl User {
         }
     }
 
+    /// Return the live cells locked by any of the three single_secp256k1 lock variants (type,
+    /// data, data1), excluding the cells carrying a type script or data and the immature
+    /// cellbase cells. `node` must run with `NodeOptions::indexer(true)`.
     pub fn get_spendable_single_secp256k1_cells(&self, node: &Node) -> Vec<CellMeta> {
-        Vec::new()
-        //let tip_number = node.get_tip_block_number();
-        //let mut live_out_points = Vec::new();
-
-        // FIXME: The Indexer has changed into a bounded one
-        //live_out_points.extend(
-        //    node.rpc_client()
-        //        .get_live_cells_by_lock_script(&self.single_secp256k1_lock_script_via_type())
-        //        .expect("indexer get_live_cells_by_lock_script"),
-        //);
-        //live_out_points.extend(
-        //    node.indexer()
-        //        .get_live_cells_by_lock_script(&self.single_secp256k1_lock_script_via_data())
-        //        .expect("indexer get_live_cells_by_lock_script"),
-        //);
-        //live_out_points.extend(
-        //    node.indexer()
-        //        .get_live_cells_by_lock_script(&self.single_secp256k1_lock_script_via_data1())
-        //        .expect("indexer get_live_cells_by_lock_script"),
-        //);
-        //
-        //live_out_points
-        //    .into_iter()
-        //    .filter_map(|out_point| {
-        //        let cell_meta = node.get_cell_meta(out_point)?;
-
-        //        let txinfo = cell_meta
-        //            .transaction_info
-        //            .as_ref()
-        //            .expect("committed tx has transaction_info");
-        //        if txinfo.is_cellbase() {
-        //            let cellbase_maturity: EpochNumberWithFraction = {
-        //                EpochNumberWithFraction::from_full_value(
-        //                    node.consensus().cellbase_maturity.into(),
-        //                )
-        //            };
-        //            // We didn't fill the block_epoch inside `fn get_cell_meta`
-        //            if txinfo.block_number + cellbase_maturity.number() * 1800 > tip_number {
-        //                return None;
-        //            }
-        //        }
-
-        //        if cell_meta.data_bytes != 0 {
-        //            return None;
-        //        }
+        self.try_get_spendable_single_secp256k1_cells(node)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
 
-        //        Some(cell_meta)
-        //    })
-        //    .collect::<Vec<_>>()
+    pub fn try_get_spendable_single_secp256k1_cells(
+        &self,
+        node: &Node,
+    ) -> Result<Vec<CellMeta>, Error> {
+        let mut cells = Vec::new();
+        for lock_script in &[
+            self.single_secp256k1_lock_script_via_type(),
+            self.single_secp256k1_lock_script_via_data(),
+            self.single_secp256k1_lock_script_via_data1(),
+        ] {
+            cells.extend(node.try_get_spendable_cells_by_lock_script(lock_script)?);
+        }
+        Ok(cells)
     }
 }
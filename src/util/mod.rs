@@ -97,3 +97,45 @@ pub fn since_from_relative_timestamp(timestamp: u64) -> u64 {
 pub fn since_from_absolute_timestamp(timestamp: u64) -> u64 {
     FLAG_SINCE_TIMESTAMP | timestamp
 }
+
+/// Return the IP of the p2p address, e.g. "127.0.0.1" of "/ip4/127.0.0.1/tcp/8115"
+pub fn p2p_ip(p2p_address: &str) -> Option<String> {
+    let mut parts = p2p_address.split('/').skip(1);
+    match (parts.next(), parts.next()) {
+        (Some("ip4"), Some(ip)) | (Some("ip6"), Some(ip)) => Some(ip.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::p2p_ip;
+
+    #[test]
+    fn test_p2p_ip4() {
+        assert_eq!(
+            p2p_ip("/ip4/127.0.0.1/tcp/8115"),
+            Some("127.0.0.1".to_string())
+        );
+        assert_eq!(
+            p2p_ip("/ip4/192.168.1.2/tcp/8115/p2p/QmSRcPqUn4aQrKGXyCDjP9hJ6ZZkvA6DKVvxb1Co9jfQHm"),
+            Some("192.168.1.2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_p2p_ip6() {
+        assert_eq!(p2p_ip("/ip6/::1/tcp/8115"), Some("::1".to_string()));
+        assert_eq!(
+            p2p_ip("/ip6/fe80::1ff:fe23:4567:890a/tcp/8115"),
+            Some("fe80::1ff:fe23:4567:890a".to_string())
+        );
+    }
+
+    #[test]
+    fn test_p2p_ip_unsupported() {
+        assert_eq!(p2p_ip("/dns4/localhost/tcp/8115"), None);
+        assert_eq!(p2p_ip("127.0.0.1:8115"), None);
+        assert_eq!(p2p_ip(""), None);
+    }
+}